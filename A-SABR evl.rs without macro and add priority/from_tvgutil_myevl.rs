@@ -0,0 +1,15 @@
+//! tvgutil plans with the priority EVLManager of myevl.rs, needed by the evaluation harness (`PriorityEVLManager`).
+// The fields of TVGUtilContactData are private to from_tvgutil_file.rs, so this file is included at its end:
+// include!("from_tvgutil_myevl.rs");
+
+use crate::contact_manager::myevl::EVLManager as PriorityEVLManager;
+
+/// A tvgutil contact has no MAVs, they are `DEFAULT_MAV_RATIOS` of the contact volume.
+/// The 10, 7 and 3 seconds of data of `new_legacy` are smaller than most bundles of the evaluation on LEO plans.
+impl<NM: NodeManager> FromTVGUtilContactData<NM, PriorityEVLManager> for PriorityEVLManager {
+    fn tvg_convert(data: TVGUtilContactData) -> Option<Contact<NM, PriorityEVLManager>> {
+        let contact_info = ContactInfo::new(data.tx_node, data.rx_node, data.tx_start, data.tx_end);
+        let manager = PriorityEVLManager::from_volume(data.data_rate, data.delay, data.tx_end - data.tx_start);
+        Contact::try_new(contact_info, manager)
+    }
+}
@@ -9,12 +9,17 @@ use crate::{
     types::{Date, DataRate, Duration, Volume, Priority},
 };
 
+/// MAV of each priority as a ratio of the contact volume, for the plans without MAVs (tvgutil),
+/// same as `convert --marker evl --mav 1,0.7,0.3` in the evaluation.
+pub const DEFAULT_MAV_RATIOS: [f64; 3] = [1.0, 0.7, 0.3];
+
 /// A volume manager implementing the Effective Volume Limit (EVL) logic with priority support.
 /// 
 /// Compilation rules:
 /// * Consider the delay to offset the earliest transmission opportunity: `false`.
 /// * Update automatically the booked volume (i.e. queue) upon schedule: `true`. No enqueue or dequeue methods.
 #[cfg_attr(feature = "debug", derive(Debug))]
+#[derive(Clone)]
 pub struct EVLManager {
     /// The data transmission rate.
     pub rate: DataRate,
@@ -53,6 +58,13 @@ impl EVLManager {
         let default_mav = [rate * 10.0, rate * 7.0, rate * 3.0];
         Self::new(rate, delay, default_mav)
     }
+
+    /// Creates a new `EVLManager` whose MAVs are `DEFAULT_MAV_RATIOS` of the volume of a contact lasting `duration`.
+    pub fn from_volume(rate: DataRate, delay: Duration, duration: Duration) -> Self {
+        let volume = rate * duration;
+        let mav = DEFAULT_MAV_RATIOS.map(|ratio| ratio * volume);
+        Self::new(rate, delay, mav)
+    }
     
     /// Get Maximum Available Volume for a given priority, 
     /// Returns the MAV value for the specified priority level.
@@ -132,6 +144,7 @@ impl ContactManager for EVLManager {
         // TODO: Since we check start/end/expiration time in dry_run_rx already,
         // it's reasonable to check C.EVL here as well,
         // verify if this is better than having a negative R.EVL.
+        // evl_diff.rs in the standalone module flags the bundles where this check and R.EVL disagree.
         let max_volume = (tx_end - tx_start) * self.rate;
         if bundle.size > max_volume.min(self.get_mav(bundle.priority)) {
             return None;
@@ -0,0 +1,88 @@
+//! Resumable grid search: finished (plan, contact manager, router, parameter set) combinations are appended
+//! to `<results_dir>/checkpoint.txt` and skipped on rerun. Result files are replaced atomically.
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::config::EvalConfig;
+
+pub struct Checkpoint {
+    path: PathBuf,
+    done: HashSet<String>,
+}
+
+impl Checkpoint {
+    pub fn open(results_dir: &str) -> Result<Self, String> {
+        let path = Path::new(results_dir).join("checkpoint.txt");
+        let mut done = HashSet::new();
+        if path.is_file() {
+            let text = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            // a crash while appending leaves a last line without newline, it is not finished
+            // and is cut off, so that the next key starts on its own line
+            let complete = match text.rfind('\n') {
+                Some(pos) => &text[..pos + 1],
+                None => "",
+            };
+            if complete.len() < text.len() {
+                OpenOptions::new()
+                    .write(true)
+                    .open(&path)
+                    .and_then(|f| f.set_len(complete.len() as u64))
+                    .map_err(|e| format!("{}: {}", path.display(), e))?;
+            }
+            done.extend(complete.lines().filter(|l| !l.is_empty()).map(str::to_string));
+        }
+        Ok(Self { path, done })
+    }
+
+    /// One line per finished combination, the parameter set is the config columns of the CSV and the seed.
+    pub fn key(cp_file: &str, cm_label: &str, router: &str, config: &EvalConfig, seed: u64) -> String {
+        format!("{}\t{}\t{}\t{},{}", cp_file, cm_label, router, config.csv_values(), seed)
+    }
+
+    /// Whether every contact manager and router of the config is done for this plan.
+    pub fn plan_done(&self, cp_file: &str, config: &EvalConfig, seed: u64) -> bool {
+        config.contact_managers.iter().all(|cm_label| {
+            config
+                .routers
+                .iter()
+                .all(|router| self.is_done(&Self::key(cp_file, cm_label, router, config, seed)))
+        })
+    }
+
+    pub fn is_done(&self, key: &str) -> bool {
+        self.done.contains(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.done.len()
+    }
+
+    pub fn mark_done(&mut self, key: String) -> Result<(), String> {
+        let mut f = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("{}: {}", self.path.display(), e))?;
+        writeln!(f, "{}", key)
+            .and_then(|_| f.sync_data())
+            .map_err(|e| format!("{}: {}", self.path.display(), e))?;
+        self.done.insert(key);
+        Ok(())
+    }
+}
+
+/// Writes to `<path>.tmp` then renames, so that a crash never leaves a truncated file behind.
+pub fn write_atomic(path: &Path, contents: &str) -> Result<(), String> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let mut f = File::create(&tmp).map_err(|e| format!("{}: {}", tmp.display(), e))?;
+    f.write_all(contents.as_bytes())
+        .and_then(|_| f.sync_all())
+        .map_err(|e| format!("{}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, path).map_err(|e| format!("{}: {}", path.display(), e))
+}
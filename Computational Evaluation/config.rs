@@ -0,0 +1,374 @@
+//! Evaluation parameters, from the defaults below, then an optional TOML experiment file
+//! (`--config <file>`), then the command line flags.
+//!
+//! ```toml
+//! # data_rate = 9600.0  # fixed rate, the rates of the plan by default
+//! bundle_max_count = 100000
+//! bundle_size_min_ratio = 0.01
+//! bundle_size_max_ratio = 0.1
+//! elapse_cap_s = 4.0
+//! throttle_on = true
+//! export_csv = false
+//! export_jsonl = true
+//! results_dir = "../results"
+//! routers = ["SpsnHybridParenting", "VolCgrNodeParenting"]
+//! contact_managers = ["EVLManager", "QDManager", "SegmentationManager"]
+//! # priority_mix = [0.2, 0.3, 0.5]  # weights of priorities 0 (highest), 1, 2, every bundle has priority 1 when empty
+//!
+//! [traffic]
+//! model = "poisson"  # start, poisson, periodic or bursty
+//! seed = 1
+//! burst_size = 100
+//! burst_spread_s = 60.0
+//!
+//! [lifetime]
+//! model = "exponential"  # none, fixed, uniform or exponential
+//! lifetime_s = 3600.0
+//! min_lifetime_s = 0.0
+//!
+//! [spsn]
+//! check_size = true
+//! check_priority = false
+//! max_entries = 10
+//! ```
+use std::{fmt, fs, time::Duration};
+
+use a_sabr::routing::aliases::SpsnOptions;
+use serde::{Deserialize, Serialize};
+
+use crate::traffic::{LifetimeConfig, LifetimeModel, TrafficConfig, TrafficModel};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SpsnConfig {
+    pub check_size: bool,
+    pub check_priority: bool,
+    pub max_entries: usize,
+}
+
+impl Default for SpsnConfig {
+    fn default() -> Self {
+        Self {
+            check_size: true,
+            check_priority: false,
+            max_entries: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EvalConfig {
+    /// Fixed data rate used to size the bundles, the rates of the plan file when None.
+    pub data_rate: Option<f64>,
+    /// Max number of bundles that a router will route.
+    pub bundle_max_count: usize,
+    /// Bundle sizes are drawn in [min_ratio, max_ratio] * average contact volume.
+    pub bundle_size_min_ratio: f64,
+    pub bundle_size_max_ratio: f64,
+    /// A router stops routing after this time when throttle_on.
+    pub elapse_cap_s: f64,
+    pub throttle_on: bool,
+    pub export_csv: bool,
+    /// Append a structured record of every run to <results_dir>/runs.jsonl.
+    pub export_jsonl: bool,
+    pub results_dir: String,
+    pub routers: Vec<String>,
+    pub contact_managers: Vec<String>,
+    /// Weights of the bundle priorities 0 (highest), 1 and 2, every bundle has priority 1 when empty.
+    /// A mix turns check_priority on and only runs with the PriorityEVLManager.
+    pub priority_mix: Vec<f64>,
+    pub spsn: SpsnConfig,
+    /// When the bundles are created and routed.
+    pub traffic: TrafficConfig,
+    /// When the bundles expire.
+    pub lifetime: LifetimeConfig,
+}
+
+impl Default for EvalConfig {
+    fn default() -> Self {
+        Self {
+            data_rate: None,
+            bundle_max_count: 1e5 as usize,
+            bundle_size_min_ratio: 0.01,
+            bundle_size_max_ratio: 0.1,
+            elapse_cap_s: 4.0,
+            throttle_on: true,
+            export_csv: false,
+            export_jsonl: true,
+            results_dir: "../results".to_string(),
+            routers: vec![
+                "SpsnHybridParenting".to_string(),
+                "SpsnNodeParenting".to_string(),
+                "SpsnContactParenting".to_string(),
+                "VolCgrHybridParenting".to_string(),
+                "VolCgrNodeParenting".to_string(),
+                "VolCgrContactParenting".to_string(),
+                // "CgrFirstEndingHybridParenting", "CgrFirstEndingNodeParenting", "CgrFirstEndingContactParenting",
+                // "CgrFirstDepletedHybridParenting", "CgrFirstDepletedNodeParenting", "CgrFirstDepletedContactParenting",
+            ],
+            // "PriorityEVLManager" is the myevl.rs manager, its MAVs are 1, 0.7 and 0.3 of the contact volume (FromTVGUtilContactData impl in myevl.md)
+            contact_managers: vec![
+                "EVLManager".to_string(),
+                "QDManager".to_string(),
+                "SegmentationManager".to_string(),
+            ],
+            priority_mix: Vec::new(),
+            spsn: SpsnConfig::default(),
+            traffic: TrafficConfig::default(),
+            lifetime: LifetimeConfig::default(),
+        }
+    }
+}
+
+pub const FLAGS_USAGE: &str = "Options:
+    --config <file.toml>      experiment file, overridden by the flags below
+    --data-rate <f64>         fixed data rate used to size the bundles (the rates of the plan)
+    --bundles <usize>         max number of bundles per router (100000)
+    --size-min-ratio <f64>    min bundle size / average contact volume (0.01)
+    --size-max-ratio <f64>    max bundle size / average contact volume (0.1)
+    --elapse-cap <secs>       routing time cap per router (4)
+    --throttle <bool>         stop routing after the elapse cap (true)
+    --csv <bool>              export the results as CSV (false)
+    --jsonl <bool>            append a record of every run to <results-dir>/runs.jsonl (true)
+    --results-dir <dir>       where the CSV and records are written (../results)
+    --routers <a,b,...>       routers to measure
+    --managers <a,b,...>      contact managers: EVLManager, QDManager, SegmentationManager, PriorityEVLManager
+    --priority-mix <w0,w1,w2> weights of the bundle priorities, 0 the highest (all priority 1)
+    --check-size <bool>       SpsnOptions.check_size (true)
+    --check-priority <bool>   SpsnOptions.check_priority (false)
+    --max-entries <usize>     SpsnOptions.max_entries (10)
+    --traffic <model>         bundle creation times: start, poisson, periodic, bursty (start)
+    --traffic-seed <u64>      seed of the creation times (1)
+    --burst-size <usize>      bundles per burst (100)
+    --burst-spread <secs>     bundles of a burst are created within this time (60)
+    --lifetime <model>        bundle lifetimes: none, fixed, uniform, exponential (none: the last contact end)
+    --lifetime-s <secs>       fixed, max (uniform) or mean (exponential) lifetime (3600)
+    --min-lifetime <secs>     min lifetime (uniform) (0)";
+
+impl EvalConfig {
+    /// Builds the configuration from the command line, flags are removed from `args`
+    /// so that only the positional arguments are left.
+    pub fn from_args(args: &mut Vec<String>) -> Result<Self, String> {
+        let (flags, positional) = split_flags(&args[1..])?;
+        args.truncate(1);
+        args.extend(positional);
+        let mut config = match flags.iter().find(|(flag, _)| flag == "--config") {
+            Some((_, path)) => Self::from_toml_file(path)?,
+            None => Self::default(),
+        };
+        for (flag, value) in flags.iter() {
+            config.set(flag, value)?;
+        }
+        config.check()?;
+        Ok(config)
+    }
+
+    pub fn from_toml_file(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    fn set(&mut self, flag: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("Invalid value for {}: {}", flag, value);
+        match flag {
+            "--config" => {}
+            "--data-rate" => self.data_rate = Some(value.parse().map_err(|_| invalid())?),
+            "--bundles" => self.bundle_max_count = parse_count(value).ok_or_else(invalid)?,
+            "--size-min-ratio" => self.bundle_size_min_ratio = value.parse().map_err(|_| invalid())?,
+            "--size-max-ratio" => self.bundle_size_max_ratio = value.parse().map_err(|_| invalid())?,
+            "--elapse-cap" => self.elapse_cap_s = value.parse().map_err(|_| invalid())?,
+            "--throttle" => self.throttle_on = value.parse().map_err(|_| invalid())?,
+            "--csv" => self.export_csv = value.parse().map_err(|_| invalid())?,
+            "--jsonl" => self.export_jsonl = value.parse().map_err(|_| invalid())?,
+            "--results-dir" => self.results_dir = value.to_string(),
+            "--routers" => self.routers = split_list(value),
+            "--managers" => self.contact_managers = split_list(value),
+            "--priority-mix" => {
+                self.priority_mix = split_list(value)
+                    .iter()
+                    .map(|w| w.parse::<f64>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| invalid())?
+            }
+            "--check-size" => self.spsn.check_size = value.parse().map_err(|_| invalid())?,
+            "--check-priority" => self.spsn.check_priority = value.parse().map_err(|_| invalid())?,
+            "--max-entries" => self.spsn.max_entries = value.parse().map_err(|_| invalid())?,
+            "--traffic" => self.traffic.model = value.parse()?,
+            "--traffic-seed" => self.traffic.seed = value.parse().map_err(|_| invalid())?,
+            "--burst-size" => self.traffic.burst_size = value.parse().map_err(|_| invalid())?,
+            "--burst-spread" => self.traffic.burst_spread_s = value.parse().map_err(|_| invalid())?,
+            "--lifetime" => self.lifetime.model = value.parse()?,
+            "--lifetime-s" => self.lifetime.lifetime_s = value.parse().map_err(|_| invalid())?,
+            "--min-lifetime" => self.lifetime.min_lifetime_s = value.parse().map_err(|_| invalid())?,
+            _ => return Err(format!("Unknown option {}\n{}", flag, FLAGS_USAGE)),
+        }
+        Ok(())
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if self.bundle_size_min_ratio > self.bundle_size_max_ratio {
+            return Err("size-min-ratio is larger than size-max-ratio".to_string());
+        }
+        if !self.elapse_cap_s.is_finite() || self.elapse_cap_s < 0.0 {
+            return Err(format!("elapse-cap must be a finite number of seconds ≥ 0, not {}", self.elapse_cap_s));
+        }
+        if self.routers.is_empty() || self.contact_managers.is_empty() {
+            return Err("No router or no contact manager to measure".to_string());
+        }
+        if !self.priority_mix.is_empty() {
+            if self.priority_mix.len() > 3 || self.priority_mix.iter().any(|w| *w < 0.0) || self.priority_mix.iter().sum::<f64>() <= 0.0 {
+                return Err("priority-mix expects up to 3 non-negative weights, for priorities 0, 1 and 2".to_string());
+            }
+            // the other managers ignore the priorities, their per-priority metrics would mean nothing
+            if self.contact_managers.iter().any(|cm| cm != "PriorityEVLManager") {
+                return Err("priority-mix only runs with --managers PriorityEVLManager".to_string());
+            }
+        }
+        if self.traffic.burst_spread_s < 0.0 {
+            return Err("burst-spread is negative".to_string());
+        }
+        if self.lifetime.model != LifetimeModel::None
+            && (self.lifetime.lifetime_s <= 0.0 || self.lifetime.min_lifetime_s < 0.0 || self.lifetime.min_lifetime_s > self.lifetime.lifetime_s)
+        {
+            return Err("Expected 0 ≤ min-lifetime ≤ lifetime-s and a positive lifetime-s".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn elapse_cap(&self) -> Duration {
+        Duration::from_secs_f64(self.elapse_cap_s)
+    }
+
+    /// Priority checks are always on with a priority mix.
+    pub fn check_priority(&self) -> bool {
+        self.spsn.check_priority || !self.priority_mix.is_empty()
+    }
+
+    /// Spsn and VolCgr routers take SpsnOptions, the Cgr ones don't.
+    pub fn router_configs(&self) -> Vec<(String, Option<SpsnOptions>)> {
+        let options = SpsnOptions {
+            check_size: self.spsn.check_size,
+            check_priority: self.check_priority(),
+            max_entries: self.spsn.max_entries,
+        };
+        self.routers
+            .iter()
+            .map(|name| {
+                if name.starts_with("Spsn") || name.starts_with("VolCgr") {
+                    (name.clone(), Some(options.clone()))
+                } else {
+                    (name.clone(), None)
+                }
+            })
+            .collect()
+    }
+
+    /// Settings stored along the metrics of each CSV row.
+    pub fn csv_header(&self) -> &'static str {
+        "data_rate,bundle_max_count,size_min_ratio,size_max_ratio,elapse_cap_s,throttle_on,check_size,check_priority,max_entries,priority_mix,traffic,traffic_seed,burst_size,burst_spread_s,lifetime,lifetime_s,min_lifetime_s"
+    }
+
+    /// Traffic, lifetime and priority mix for the CSV file names, empty with the defaults
+    /// so that the names of the earlier runs are kept.
+    pub fn workload_suffix(&self) -> String {
+        let mut suffix = String::new();
+        match self.traffic.model {
+            TrafficModel::Start => {}
+            TrafficModel::Bursty => suffix.push_str(&format!(
+                "_bursty{}-{}x{}s",
+                self.traffic.seed, self.traffic.burst_size, self.traffic.burst_spread_s
+            )),
+            model => suffix.push_str(&format!("_{}{}", model, self.traffic.seed)),
+        }
+        match self.lifetime.model {
+            LifetimeModel::None => {}
+            LifetimeModel::Uniform => suffix.push_str(&format!(
+                "_ttl-uniform{}-{}s",
+                self.lifetime.min_lifetime_s, self.lifetime.lifetime_s
+            )),
+            model => suffix.push_str(&format!("_ttl-{}{}s", model, self.lifetime.lifetime_s)),
+        }
+        if !self.priority_mix.is_empty() {
+            suffix.push_str(&format!("_mix{}", self.priority_mix_label().replace('/', "-")));
+        }
+        suffix
+    }
+
+    /// `0.2/0.3/0.5`, `none` without mix, no comma for the CSV.
+    pub fn priority_mix_label(&self) -> String {
+        if self.priority_mix.is_empty() {
+            return "none".to_string();
+        }
+        self.priority_mix.iter().map(|w| w.to_string()).collect::<Vec<_>>().join("/")
+    }
+
+    pub fn csv_values(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.data_rate.map_or("plan".to_string(), |r| r.to_string()),
+            self.bundle_max_count,
+            self.bundle_size_min_ratio,
+            self.bundle_size_max_ratio,
+            self.elapse_cap_s,
+            self.throttle_on,
+            self.spsn.check_size,
+            self.check_priority(),
+            self.spsn.max_entries,
+            self.priority_mix_label(),
+            self.traffic.model,
+            self.traffic.seed,
+            self.traffic.burst_size,
+            self.traffic.burst_spread_s,
+            self.lifetime.model,
+            self.lifetime.lifetime_s,
+            self.lifetime.min_lifetime_s
+        )
+    }
+}
+
+impl fmt::Display for EvalConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "    data rate= {}, bundles= {}, bundle size ratios= [{}, {}],", self.data_rate.map_or("plan".to_string(), |r| r.to_string()), self.bundle_max_count, self.bundle_size_min_ratio, self.bundle_size_max_ratio)?;
+        writeln!(f, "    elapse cap= {} s, throttle= {}, export csv= {}, export jsonl= {} ({}),", self.elapse_cap_s, self.throttle_on, self.export_csv, self.export_jsonl, self.results_dir)?;
+        writeln!(f, "    SpsnOptions= {{ check_size: {}, check_priority: {}, max_entries: {} }},", self.spsn.check_size, self.check_priority(), self.spsn.max_entries)?;
+        writeln!(f, "    priority mix= {},", self.priority_mix_label())?;
+        writeln!(f, "    traffic= {}, lifetime= {},", self.traffic, self.lifetime)?;
+        writeln!(f, "    routers= [{}],", self.routers.join(", "))?;
+        write!(f, "    contact managers= [{}].", self.contact_managers.join(", "))
+    }
+}
+
+/// Splits `--flag value` pairs from the positional arguments.
+pub fn split_flags(args: &[String]) -> Result<(Vec<(String, String)>, Vec<String>), String> {
+    let mut flags: Vec<(String, String)> = Vec::new();
+    let mut positional: Vec<String> = Vec::new();
+    let mut i = 0;
+    while i < args.len() {
+        if args[i].starts_with("--") {
+            let value = args.get(i + 1).ok_or_else(|| format!("Missing value for {}", args[i]))?;
+            flags.push((args[i].clone(), value.clone()));
+            i += 2;
+        } else {
+            positional.push(args[i].clone());
+            i += 1;
+        }
+    }
+    Ok((flags, positional))
+}
+
+/// Accepts plain integers and the 1e5 notation used in the result file names.
+fn parse_count(value: &str) -> Option<usize> {
+    value
+        .parse::<usize>()
+        .ok()
+        .or_else(|| value.parse::<f64>().ok().filter(|v| *v >= 0.0).map(|v| v as usize))
+}
+
+pub fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
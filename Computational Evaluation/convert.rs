@@ -0,0 +1,357 @@
+//! Contact plan conversion between tvgutil JSON (read by TVGUtilContactPlan), the A-SABR text format
+//! (read by ASABRContactPlan, e.g. the priority binary) and ION `a contact` / `a range` commands
+//! (read by the standalone R.EVL binary), so that one scenario drives every binary of the repository.
+//! * A-SABR: `node <id> <name>` and `contact <from> <to> <start> <end> [marker] <rate> <delay> [mavs]`,
+//!   the marker may also come after `<rate> <delay>` as in priority_test.cp. MAVs are not kept when reading.
+//! * ION: nodes are numbered from 1 in the order of the vertices, with `# node <number> <name>` comments
+//!   read back as names. Times are UTC, rounded to the second, and every contact gets the `a range` of
+//!   its delay, rounded to the second as ION does. ION rates are in bytes/s, tvgutil and A-SABR ones in bits/s.
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::{
+    checkpoint::write_atomic,
+    config::{split_flags, split_list},
+    plan::{TvgContact, TvgPlan},
+    time_now,
+};
+
+pub const CONVERT_USAGE: &str = "convert <in> <out> [options]
+    --to <tvgutil|asabr|ion>  output format, from the extension of <out> by default (.json, .cp, .ion)
+    --marker <evl|qd>         A-SABR manager marker, for a Dispatcher
+    --mav <p0,p1,p2>          A-SABR MAVs of the priority levels, as ratios of the contact volume";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Tvgutil,
+    Asabr,
+    Ion,
+}
+
+impl Format {
+    fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "tvgutil" => Ok(Format::Tvgutil),
+            "asabr" => Ok(Format::Asabr),
+            "ion" => Ok(Format::Ion),
+            _ => Err(format!("Unknown format {}, expected tvgutil, asabr or ion", name)),
+        }
+    }
+
+    fn from_extension(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "json" => Some(Format::Tvgutil),
+            "cp" | "txt" => Some(Format::Asabr),
+            "ion" | "rc" | "ionrc" => Some(Format::Ion),
+            _ => None,
+        }
+    }
+
+    /// JSON by extension, ION if there is an `a contact` command, A-SABR otherwise.
+    fn detect(path: &Path, text: &str) -> Self {
+        if Format::from_extension(path) == Some(Format::Tvgutil) || text.trim_start().starts_with('{') {
+            return Format::Tvgutil;
+        }
+        let is_ion = text.lines().any(|l| {
+            let mut words = l.split_whitespace();
+            words.next() == Some("a") && words.next() == Some("contact")
+        });
+        if is_ion {
+            Format::Ion
+        } else {
+            Format::Asabr
+        }
+    }
+}
+
+fn parse_num(token: &str) -> Result<f64, String> {
+    token
+        .parse::<f64>()
+        .map_err(|_| format!("invalid number `{}`", token))
+}
+
+fn parse_node(token: &str) -> Result<usize, String> {
+    token
+        .parse::<usize>()
+        .map_err(|_| format!("invalid node id `{}`", token))
+}
+
+/// Vertices from node ids: the given names, the id itself otherwise.
+/// Names must be unique in tvgutil, a name given to several ids gets the id as suffix (`destination_3`).
+fn vertices(names: &BTreeMap<usize, String>, ids: impl Iterator<Item = usize>) -> BTreeMap<usize, String> {
+    let mut vertices = names.clone();
+    for id in ids {
+        vertices.entry(id).or_insert_with(|| id.to_string());
+    }
+    let counts = vertices.values().fold(BTreeMap::new(), |mut counts, name| {
+        *counts.entry(name.clone()).or_insert(0) += 1;
+        counts
+    });
+    for (id, name) in vertices.iter_mut() {
+        if counts[name.as_str()] > 1 {
+            *name = format!("{}_{}", name, id);
+        }
+    }
+    vertices
+}
+
+/// Builds the plan, contacts given with node ids, sorted by start on each edge.
+fn build_plan(names: &BTreeMap<usize, String>, contacts: Vec<(usize, usize, f64, f64, f64, f64)>) -> TvgPlan {
+    let vertices = vertices(names, contacts.iter().flat_map(|c| [c.0, c.1]));
+    let mut plan = TvgPlan {
+        vertices: vertices.values().cloned().collect(),
+        ..TvgPlan::default()
+    };
+    for (from, to, start, end, rate, delay) in contacts {
+        plan.push(TvgContact::new(&vertices[&from], &vertices[&to], start, end, rate, delay));
+    }
+    for contacts in plan.edges.values_mut() {
+        contacts.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap());
+    }
+    plan
+}
+
+pub fn from_asabr(text: &str, path: &str) -> Result<TvgPlan, String> {
+    let mut names: BTreeMap<usize, String> = BTreeMap::new();
+    let mut contacts = Vec::new();
+    let mut dropped_mavs = 0;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let at = |msg: &str| format!("{}:{}: {}", path, i + 1, msg);
+        match tokens.first() {
+            Some(&"node") => {
+                let id = tokens.get(1).ok_or_else(|| at("expected `node <id> [name]`"))?;
+                let id = parse_node(id).map_err(|e| at(&e))?;
+                names.insert(id, tokens.get(2).map_or(id.to_string(), |n| n.to_string()));
+                continue;
+            }
+            Some(&"contact") => {}
+            _ => continue,
+        }
+        if tokens.len() < 7 {
+            return Err(at("expected `contact <from> <to> <start> <end> <rate> <delay>`"));
+        }
+        // the manager marker is the non numeric token after <end>
+        let markers = tokens[5..].iter().filter(|t| t.parse::<f64>().is_err()).count();
+        if markers > 1 {
+            return Err(at("more than one manager marker"));
+        }
+        let values: Vec<f64> = tokens[5..].iter().filter_map(|t| t.parse::<f64>().ok()).collect();
+        if values.len() < 2 {
+            return Err(at("missing <rate> or <delay>"));
+        }
+        if values.len() > 2 {
+            dropped_mavs += 1;
+        }
+        contacts.push((
+            parse_node(tokens[1]).map_err(|e| at(&e))?,
+            parse_node(tokens[2]).map_err(|e| at(&e))?,
+            parse_num(tokens[3]).map_err(|e| at(&e))?,
+            parse_num(tokens[4]).map_err(|e| at(&e))?,
+            values[0],
+            values[1],
+        ));
+    }
+    if dropped_mavs > 0 {
+        println!("{}: warning: the MAVs of {} contacts are not kept", path, dropped_mavs);
+    }
+    Ok(build_plan(&names, contacts))
+}
+
+// from_ion, parse_ion_time and days_from_civil are copied in the cp_loader.rs of the standalone R.EVL,
+// a separate binary: these are the canonical ones, with the writing side (to_ion), keep the copy in sync.
+pub fn from_ion(text: &str, path: &str) -> Result<TvgPlan, String> {
+    let mut names: BTreeMap<usize, String> = BTreeMap::new();
+    let mut contacts = Vec::new();
+    // (start, end, from, to, owlt), applied once all the contacts are known
+    let mut ranges: Vec<(f64, f64, usize, usize, f64)> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let at = |msg: &str| format!("{}:{}: {}", path, i + 1, msg);
+        if let ["#", "node", id, name] = tokens.as_slice() {
+            names.insert(parse_node(id).map_err(|e| at(&e))?, name.to_string());
+            continue;
+        }
+        let tokens: Vec<&str> = line.split('#').next().unwrap_or("").split_whitespace().collect();
+        if tokens.len() < 2 || tokens[0] != "a" || (tokens[1] != "contact" && tokens[1] != "range") {
+            continue;
+        }
+        if tokens.len() < 7 {
+            return Err(at(&format!("expected `a {} <start> <end> <from> <to> <value>`", tokens[1])));
+        }
+        let start = parse_ion_time(tokens[2]).map_err(|e| at(&e))?;
+        let end = parse_ion_time(tokens[3]).map_err(|e| at(&e))?;
+        let from = parse_node(tokens[4]).map_err(|e| at(&e))?;
+        let to = parse_node(tokens[5]).map_err(|e| at(&e))?;
+        let value = parse_num(tokens[6]).map_err(|e| at(&e))?;
+        if tokens[1] == "contact" {
+            contacts.push((from, to, start, end, value * 8.0, 0.0)); // bytes/s to bits/s
+        } else {
+            ranges.push((start, end, from, to, value));
+        }
+    }
+    // ION ranges are symmetric unless both directions are declared
+    for contact in contacts.iter_mut() {
+        for &(start, end, from, to, owlt) in ranges.iter() {
+            let same_pair = (from == contact.0 && to == contact.1) || (from == contact.1 && to == contact.0);
+            if same_pair && start <= contact.2 && contact.2 < end {
+                contact.5 = owlt;
+                if from == contact.0 {
+                    break; // exact direction wins
+                }
+            }
+        }
+    }
+    Ok(build_plan(&names, contacts))
+}
+
+pub fn to_ion(plan: &TvgPlan) -> String {
+    let mut text = String::from("# ION contact plan, nodes numbered from 1\n");
+    for (id, name) in plan.vertices.iter().enumerate() {
+        text.push_str(&format!("# node {} {}\n", id + 1, name));
+    }
+    let number = |name: &str| plan.vertices.iter().position(|v| v == name).map(|i| i + 1);
+    let mut contacts = Vec::new();
+    let mut ranges = Vec::new();
+    for c in plan.contacts() {
+        let (Some(from), Some(to)) = (number(&c.tx_node), number(&c.rx_node)) else {
+            continue;
+        };
+        for (i, ch) in c.characteristics.iter().enumerate() {
+            let start = ch.starting_at.max(c.start).round();
+            let end = c.characteristics.get(i + 1).map_or(c.end, |n| n.starting_at).min(c.end).round();
+            if start >= end {
+                continue;
+            }
+            let (start, end) = (ion_time(start), ion_time(end));
+            contacts.push(format!("a contact {} {} {} {} {}\n", start, end, from, to, ch.bit_rate / 8.0)); // bytes/s
+            ranges.push(format!("a range {} {} {} {} {}\n", start, end, from, to, ch.delay.round()));
+        }
+    }
+    text.push('\n');
+    text.extend(contacts);
+    text.push('\n');
+    text.extend(ranges);
+    text
+}
+
+/// ION time: `+<seconds>`, plain seconds, or `yyyy/mm/dd-hh:mm:ss` UTC as unix time.
+fn parse_ion_time(token: &str) -> Result<f64, String> {
+    if let Some(rel) = token.strip_prefix('+') {
+        return parse_num(rel);
+    }
+    if !token.contains('/') {
+        return parse_num(token);
+    }
+    let err = || format!("invalid ION time `{}`", token);
+    let (date, time) = token.split_once('-').ok_or_else(err)?;
+    let d: Vec<i64> = date.split('/').map(|v| v.parse().map_err(|_| err())).collect::<Result<_, _>>()?;
+    let t: Vec<f64> = time.split(':').map(|v| v.parse().map_err(|_| err())).collect::<Result<_, _>>()?;
+    if d.len() != 3 || t.len() != 3 {
+        return Err(err());
+    }
+    Ok(days_from_civil(d[0], d[1], d[2]) as f64 * 86_400.0 + t[0] * 3600.0 + t[1] * 60.0 + t[2])
+}
+
+/// `yyyy/mm/dd-hh:mm:ss` UTC of a unix time.
+fn ion_time(unix: f64) -> String {
+    let secs = unix.round() as i64;
+    let (days, rem) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    let (y, m, d) = civil_from_days(days);
+    format!("{:04}/{:02}/{:02}-{:02}:{:02}:{:02}", y, m, d, rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Proleptic Gregorian date of a number of days since 1970-01-01.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + if m <= 2 { 1 } else { 0 }, m, d)
+}
+
+/// Reads a plan in any of the formats.
+pub fn read_plan(path: &Path) -> Result<(TvgPlan, Format), String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let name = path.to_string_lossy();
+    let format = Format::detect(path, &text);
+    let plan = match format {
+        Format::Tvgutil => serde_json::from_str(&text).map_err(|e| format!("{}: {}", name, e))?,
+        Format::Asabr => from_asabr(&text, &name)?,
+        Format::Ion => from_ion(&text, &name)?,
+    };
+    Ok((plan, format))
+}
+
+/// convert subcommand: `convert <in> <out> [options]`
+pub fn run_convert(args: &[String]) -> Result<(), String> {
+    let (flags, positional) = split_flags(args)?;
+    let mut to: Option<Format> = None;
+    let mut marker: Option<String> = None;
+    let mut mav: Option<[f64; 3]> = None;
+    for (flag, value) in flags.iter() {
+        let invalid = || format!("Invalid value for {}: {}", flag, value);
+        match flag.as_str() {
+            "--to" => to = Some(Format::from_name(value)?),
+            "--marker" => marker = Some(value.clone()),
+            "--mav" => {
+                let ratios: Vec<f64> = split_list(value)
+                    .iter()
+                    .map(|r| r.parse().map_err(|_| invalid()))
+                    .collect::<Result<_, _>>()?;
+                mav = Some(ratios.try_into().map_err(|_| invalid())?);
+            }
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+    let [input, out] = positional.as_slice() else {
+        return Err("Expected <in> <out>".to_string());
+    };
+    let out = Path::new(out);
+    let to = to
+        .or_else(|| Format::from_extension(out))
+        .ok_or_else(|| format!("Unknown format of {}, use --to", out.display()))?;
+    if to != Format::Asabr && (marker.is_some() || mav.is_some()) {
+        return Err("--marker and --mav are for the A-SABR format".to_string());
+    }
+    if mav.is_some() && marker.is_none() {
+        return Err("--mav needs a --marker".to_string());
+    }
+    let (plan, from) = read_plan(Path::new(input))?;
+    println!(
+        "{}, Converting {} ({:?}): {} nodes, {} contacts, to {:?}.",
+        time_now(),
+        input,
+        from,
+        plan.vertices.len(),
+        plan.contact_count(),
+        to
+    );
+    match to {
+        Format::Tvgutil => plan.write_checked(out),
+        Format::Asabr => plan.write_asabr_checked(out, marker.as_deref(), mav),
+        Format::Ion => {
+            if let Some(dir) = out.parent().filter(|d| !d.as_os_str().is_empty()) {
+                fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+            }
+            write_atomic(out, &to_ion(&plan))?;
+            println!("{}, {} written.", time_now(), out.display());
+            Ok(())
+        }
+    }
+}
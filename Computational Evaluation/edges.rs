@@ -0,0 +1,272 @@
+//! Failure rate edges: for every node count (and seed) of a grid, the smallest horizon where the failure
+//! rate of each (contact manager, router) drops to the threshold or below, found by bisection over the
+//! plans of the grid that exist. The failure rate is assumed to decrease with the horizon.
+//!
+//! A plan is measured once for all the routers and contact managers of the config, so a node count costs
+//! about log2(plans) runs instead of all of them. The edges are printed like the README notes and
+//! written to `<results_dir>/edges.csv`.
+//!
+//! Node counts are searched in increasing order and the edge horizon is expected not to grow with the
+//! node count (as in every grid so far): the search of a router stops at the edge found with fewer nodes.
+//! When no plan up to that bound is below the threshold, the search is run again on every horizon.
+//! With a `master` plan in the grid file, only the plans probed by the search are sliced from it.
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+};
+
+use crate::{
+    checkpoint::write_atomic,
+    config::EvalConfig,
+    grid::{GridConfig, GridPlan},
+    results::RunRecord,
+    run_plan, time_now,
+};
+
+/// One side of an edge.
+#[derive(Debug, Clone)]
+pub struct EdgeSide {
+    pub hours: u32,
+    pub contacts: usize,
+    pub fail_rate: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Edge {
+    pub nodes: u32,
+    pub seed: u64,
+    pub contact_manager: String,
+    pub router: String,
+    /// Longest horizon measured above the threshold, None if the first plan is already below.
+    pub above: Option<EdgeSide>,
+    /// Shortest horizon measured at or below the threshold, None if the last plan is still above.
+    pub below: Option<EdgeSide>,
+}
+
+impl Edge {
+    pub fn describe(&self, max_fail: f64) -> String {
+        let name = format!("{} ({})", self.router, self.contact_manager);
+        match (&self.above, &self.below) {
+            (Some(a), Some(b)) => format!(
+                "{}% failure rate achieved for {} between {} nodes, {}-{}h({}-{} contacts)",
+                max_fail * 100.0, name, self.nodes, a.hours, b.hours, a.contacts, b.contacts
+            ),
+            (None, Some(b)) => format!(
+                "{}% failure rate achieved for {} with {} nodes from the first plan, {}h({} contacts)",
+                max_fail * 100.0, name, self.nodes, b.hours, b.contacts
+            ),
+            (Some(a), None) => format!(
+                "{}% failure rate not achieved for {} with {} nodes up to {}h({} contacts, {:.2}%)",
+                max_fail * 100.0, name, self.nodes, a.hours, a.contacts, a.fail_rate * 100.0
+            ),
+            (None, None) => format!("No plan measured for {} with {} nodes", name, self.nodes),
+        }
+    }
+}
+
+/// Records of the plans measured so far, by plan path. None for a plan that failed.
+type Measured = HashMap<String, Option<Vec<RunRecord>>>;
+
+fn measure(plan: &GridPlan, config: &EvalConfig, measured: &mut Measured) -> Option<Vec<RunRecord>> {
+    let cp_file = plan.path.to_string_lossy().to_string();
+    if let Some(records) = measured.get(&cp_file) {
+        return records.clone();
+    }
+    println!("\n{}, Bisecting {} nodes: {} {}", time_now(), plan.nodes, cp_file, plan.seed);
+    // a broken plan must not stop the search, A-SABR panics on some of them
+    let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+        plan.prepare().and_then(|_| run_plan(&cp_file, plan.seed, config, None))
+    }));
+    let records = match outcome {
+        Ok(Ok(records)) => Some(records),
+        Ok(Err(e)) => {
+            println!("ERR: {}: {}", cp_file, e);
+            None
+        }
+        Err(_) => {
+            println!("ERR: {}: panicked", cp_file);
+            None
+        }
+    };
+    measured.insert(cp_file, records.clone());
+    records
+}
+
+fn side(plan: &GridPlan, records: &[RunRecord], cm: &str, router: &str) -> Option<EdgeSide> {
+    records
+        .iter()
+        .find(|r| r.contact_manager == cm && r.router == router)
+        .map(|r| EdgeSide {
+            hours: plan.hours,
+            contacts: r.contacts,
+            fail_rate: r.metrics.fail_rate,
+        })
+}
+
+/// Bisection over the plans of one node count sorted by horizon, plans that fail are dropped.
+fn bisect(
+    plans: &[GridPlan],
+    config: &EvalConfig,
+    cm: &str,
+    router: &str,
+    max_fail: f64,
+    measured: &mut Measured,
+) -> (Option<EdgeSide>, Option<EdgeSide>) {
+    let mut plans: Vec<&GridPlan> = plans.iter().collect();
+    let mut at = |plans: &[&GridPlan], i: usize| -> Option<EdgeSide> {
+        let records = measure(plans[i], config, measured)?;
+        side(plans[i], &records, cm, router)
+    };
+    // the first and last plans that can be measured
+    let mut first = None;
+    while !plans.is_empty() {
+        match at(&plans, 0) {
+            Some(s) => {
+                first = Some(s);
+                break;
+            }
+            None => {
+                plans.remove(0);
+            }
+        }
+    }
+    let Some(first) = first else { return (None, None) };
+    if first.fail_rate <= max_fail {
+        return (None, Some(first));
+    }
+    let mut last = None;
+    while plans.len() > 1 {
+        let i = plans.len() - 1;
+        match at(&plans, i) {
+            Some(s) => {
+                last = Some(s);
+                break;
+            }
+            None => {
+                plans.pop();
+            }
+        }
+    }
+    let Some(last) = last else { return (Some(first), None) };
+    if last.fail_rate > max_fail {
+        return (Some(last), None);
+    }
+    // invariant: plans[lo] above the threshold, plans[hi] at or below
+    let (mut lo, mut hi) = (0, plans.len() - 1);
+    let (mut above, mut below) = (first, last);
+    while hi - lo > 1 {
+        let mid = (lo + hi) / 2;
+        match at(&plans, mid) {
+            Some(s) if s.fail_rate <= max_fail => {
+                hi = mid;
+                below = s;
+            }
+            Some(s) => {
+                lo = mid;
+                above = s;
+            }
+            None => {
+                plans.remove(mid);
+                hi -= 1;
+            }
+        }
+    }
+    (Some(above), Some(below))
+}
+
+/// edges subcommand: `edges [options] <grid.toml> [max failure %]`, 0% by default.
+pub fn run_edges(grid_file: &str, max_fail: f64, base: &EvalConfig) -> Result<(), String> {
+    let grid = GridConfig::from_toml_file(grid_file)?;
+    fs::create_dir_all(&base.results_dir).map_err(|e| format!("{}: {}", base.results_dir, e))?;
+    // (nodes, seed) -> plans by horizon
+    let mut groups: Vec<((u32, u64), Vec<GridPlan>)> = Vec::new();
+    for plan in grid.plans() {
+        if !plan.available() {
+            println!("ERR: File not found: {}", plan.master.as_ref().unwrap_or(&plan.path).display());
+            continue;
+        }
+        match groups.iter_mut().find(|(k, _)| *k == (plan.nodes, plan.seed)) {
+            Some((_, plans)) => plans.push(plan),
+            None => groups.push(((plan.nodes, plan.seed), vec![plan])),
+        }
+    }
+    for (_, plans) in groups.iter_mut() {
+        plans.sort_by_key(|p| p.hours);
+    }
+
+    let mut csv = String::from(
+        "nodes,seed,contact_manager,router,bundle_max_count,elapse_cap_s,hours_above,contacts_above,fail_above,hours_below,contacts_below,fail_below\n",
+    );
+    let mut lines: Vec<String> = Vec::new();
+    let mut runs = 0;
+    groups.sort_by_key(|((nodes, seed), _)| (*seed, *nodes));
+    for config in grid.eval_configs(base).iter() {
+        // (seed, contact manager, router) -> edge horizon with the previous node count
+        let mut bounds: HashMap<(u64, String, String), u32> = HashMap::new();
+        for ((nodes, seed), plans) in groups.iter() {
+            let mut measured = Measured::new();
+            for cm in config.contact_managers.iter() {
+                for router in config.routers.iter() {
+                    let bound_key = (*seed, cm.clone(), router.clone());
+                    let candidates: Vec<GridPlan> = match bounds.get(&bound_key) {
+                        Some(&bound) => plans.iter().filter(|p| p.hours <= bound).cloned().collect(),
+                        None => plans.clone(),
+                    };
+                    let (mut above, mut below) = bisect(&candidates, config, cm, router, max_fail, &mut measured);
+                    if below.is_none() && candidates.len() < plans.len() {
+                        // the edge moved past the bound, search the longer horizons too
+                        (above, below) = bisect(plans, config, cm, router, max_fail, &mut measured);
+                    }
+                    if let Some(b) = &below {
+                        bounds.insert(bound_key, b.hours);
+                    }
+                    let edge = Edge {
+                        nodes: *nodes,
+                        seed: *seed,
+                        contact_manager: cm.clone(),
+                        router: router.clone(),
+                        above,
+                        below,
+                    };
+                    let cols = |s: &Option<EdgeSide>| match s {
+                        Some(s) => format!("{},{},{:.4}", s.hours, s.contacts, s.fail_rate),
+                        None => ",,".to_string(),
+                    };
+                    writeln!(
+                        csv,
+                        "{},{},{},{},{},{},{},{}",
+                        nodes, seed, cm, router, config.bundle_max_count, config.elapse_cap_s,
+                        cols(&edge.above), cols(&edge.below)
+                    )
+                    .unwrap();
+                    lines.push(format!(
+                        "(1e{} bundles, elapse {} s, seed {}) {}",
+                        (config.bundle_max_count as f64).log10().round() as usize,
+                        config.elapse_cap_s,
+                        seed,
+                        edge.describe(max_fail)
+                    ));
+                }
+            }
+            runs += measured.len();
+            println!(
+                "{}, {} nodes: {} of {} plans measured.",
+                time_now(),
+                nodes,
+                measured.len(),
+                plans.len()
+            );
+        }
+    }
+    println!("\n{}, Edges found with {} plan runs:", time_now(), runs);
+    for line in lines.iter() {
+        println!("{}", line);
+    }
+    let path = Path::new(&base.results_dir).join("edges.csv");
+    write_atomic(&path, &csv)?;
+    println!("Edges written to {}", path.display());
+    Ok(())
+}
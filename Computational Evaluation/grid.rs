@@ -0,0 +1,300 @@
+//! Grid search driver, replaces grid-search/run_asabr.sh and run_asabr.bat.
+//! Every plan of the grid is run in-process with every (bundle count, elapse cap) pair,
+//! missing or broken plans are reported and skipped.
+//! With `resume` (default), finished runs are recorded in `<results_dir>/checkpoint.txt` and skipped on rerun.
+//! With `master`, the plans are not generated one by one: each one is sliced on demand from the first
+//! `hours` of the master plan of its node count and seed, into `slices_dir` (under plans_dir).
+//!
+//! ```toml
+//! plans_dir = "."
+//! # {nodes}, {hours} and {seed} are replaced, run_asabr.bat plans have no seed: "nodes_{nodes}/02_ptvg_{nodes}_{hours}h.json"
+//! pattern = "nodes_{nodes}/02_ptvg_{nodes}_{hours}h_{seed}.json"
+//! nodes = { start = 18, end = 30, step = 2 }
+//! hours = { start = 24, end = 72, step = 4 }
+//! seeds = [1]
+//! bundle_counts = [100000]
+//! elapse_caps = [4.0]
+//! resume = true
+//! # master = "nodes_{nodes}/02_ptvg_{nodes}_168h_{seed}.json"
+//! # slices_dir = "slices"
+//! ```
+use std::{
+    cell::RefCell,
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+
+use crate::{checkpoint::Checkpoint, config::EvalConfig, plan::TvgPlan, run_plan, time_now};
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Range {
+    pub start: u32,
+    pub end: u32,
+    pub step: u32,
+}
+
+impl Range {
+    /// Values from start to end included, like `seq start step end`.
+    pub fn values(&self) -> Vec<u32> {
+        (self.start..=self.end).step_by(self.step.max(1) as usize).collect()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GridConfig {
+    #[serde(default = "default_plans_dir")]
+    pub plans_dir: String,
+    #[serde(default = "default_pattern")]
+    pub pattern: String,
+    pub nodes: Range,
+    pub hours: Range,
+    #[serde(default = "default_seeds")]
+    pub seeds: Vec<u64>,
+    /// Defaults to the bundle_max_count of the evaluation config.
+    #[serde(default)]
+    pub bundle_counts: Vec<usize>,
+    /// Defaults to the elapse_cap_s of the evaluation config.
+    #[serde(default)]
+    pub elapse_caps: Vec<f64>,
+    #[serde(default = "default_resume")]
+    pub resume: bool,
+    /// Long plan to slice the plans from, same placeholders as pattern except {hours}.
+    #[serde(default)]
+    pub master: Option<String>,
+    #[serde(default = "default_slices_dir")]
+    pub slices_dir: String,
+}
+
+fn default_plans_dir() -> String {
+    ".".to_string()
+}
+
+fn default_pattern() -> String {
+    "nodes_{nodes}/02_ptvg_{nodes}_{hours}h_{seed}.json".to_string()
+}
+
+fn default_seeds() -> Vec<u64> {
+    vec![1]
+}
+
+fn default_resume() -> bool {
+    true
+}
+
+fn default_slices_dir() -> String {
+    "slices".to_string()
+}
+
+/// One plan of the grid.
+#[derive(Debug, Clone)]
+pub struct GridPlan {
+    pub nodes: u32,
+    pub hours: u32,
+    pub seed: u64,
+    pub path: PathBuf,
+    /// Master plan that path is sliced from, when the grid has one.
+    pub master: Option<PathBuf>,
+}
+
+impl GridPlan {
+    /// Whether the plan, or the master it is sliced from, exists.
+    pub fn available(&self) -> bool {
+        match &self.master {
+            Some(master) => master.is_file(),
+            None => self.path.is_file(),
+        }
+    }
+
+    /// Writes the slice of the master if it does not exist yet.
+    pub fn prepare(&self) -> Result<(), String> {
+        let Some(master) = &self.master else { return Ok(()) };
+        if self.path.is_file() {
+            return Ok(());
+        }
+        TvgPlan::from_file(master)?
+            .window(0.0, self.hours as f64 * 3600.0)
+            .map_err(|e| format!("{}: {}", master.display(), e))?
+            .to_file(&self.path)
+    }
+}
+
+impl GridConfig {
+    pub fn from_toml_file(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let grid: Self = toml::from_str(&text).map_err(|e| format!("{}: {}", path, e))?;
+        if let Some(cap) = grid.elapse_caps.iter().find(|c| !c.is_finite() || **c < 0.0) {
+            return Err(format!("{}: elapse cap must be a finite number of seconds ≥ 0, not {}", path, cap));
+        }
+        Ok(grid)
+    }
+
+    /// All the plans of the grid in the order of run_asabr.sh, found or not.
+    pub fn plans(&self) -> Vec<GridPlan> {
+        let mut plans = Vec::new();
+        for nodes in self.nodes.values() {
+            for &seed in self.seeds.iter() {
+                for hours in self.hours.values() {
+                    let fill = |pattern: &str| {
+                        pattern
+                            .replace("{nodes}", &nodes.to_string())
+                            .replace("{hours}", &hours.to_string())
+                            .replace("{seed}", &seed.to_string())
+                    };
+                    let plan = match &self.master {
+                        Some(master) => {
+                            let master = Path::new(&self.plans_dir).join(fill(master));
+                            let stem = master.file_stem().unwrap_or_default().to_string_lossy().to_string();
+                            GridPlan {
+                                nodes,
+                                hours,
+                                seed,
+                                path: Path::new(&self.plans_dir)
+                                    .join(&self.slices_dir)
+                                    .join(format!("{}_{}h.json", stem, hours)),
+                                master: Some(master),
+                            }
+                        }
+                        None => GridPlan {
+                            nodes,
+                            hours,
+                            seed,
+                            path: Path::new(&self.plans_dir).join(fill(&self.pattern)),
+                            master: None,
+                        },
+                    };
+                    plans.push(plan);
+                }
+            }
+        }
+        plans
+    }
+
+    /// The evaluation config of every (bundle count, elapse cap) pair.
+    pub fn eval_configs(&self, base: &EvalConfig) -> Vec<EvalConfig> {
+        let counts = if self.bundle_counts.is_empty() {
+            vec![base.bundle_max_count]
+        } else {
+            self.bundle_counts.clone()
+        };
+        let caps = if self.elapse_caps.is_empty() {
+            vec![base.elapse_cap_s]
+        } else {
+            self.elapse_caps.clone()
+        };
+        let mut configs = Vec::new();
+        for &bundle_max_count in counts.iter() {
+            for &elapse_cap_s in caps.iter() {
+                configs.push(EvalConfig {
+                    bundle_max_count,
+                    elapse_cap_s,
+                    ..base.clone()
+                });
+            }
+        }
+        configs
+    }
+}
+
+pub fn run_grid(grid_file: &str, base: &EvalConfig) -> Result<(), String> {
+    let grid = GridConfig::from_toml_file(grid_file)?;
+    let (plans, missing): (Vec<GridPlan>, Vec<GridPlan>) =
+        grid.plans().into_iter().partition(|p| p.available());
+    for plan in missing.iter() {
+        println!("ERR: File not found: {}", plan.master.as_ref().unwrap_or(&plan.path).display());
+    }
+    let configs = grid.eval_configs(base);
+    let total = plans.len() * configs.len();
+    println!(
+        "{}, Grid {}: {} plans found ({} missing), {} parameter sets, {} runs.",
+        time_now(),
+        grid_file,
+        plans.len(),
+        missing.len(),
+        configs.len(),
+        total
+    );
+    fs::create_dir_all(&base.results_dir).map_err(|e| format!("{}: {}", base.results_dir, e))?;
+    let checkpoint = match grid.resume {
+        true => {
+            let checkpoint = Checkpoint::open(&base.results_dir)?;
+            println!("{}, Resuming with {} finished runs in the checkpoint.", time_now(), checkpoint.len());
+            Some(RefCell::new(checkpoint))
+        }
+        false => None,
+    };
+
+    let started = Instant::now();
+    let mut done = 0;
+    let mut ran = 0; // runs actually measured, for the ETA
+    let mut failed: Vec<String> = Vec::new();
+    for plan in plans.iter() {
+        for config in configs.iter() {
+            done += 1;
+            println!(
+                "\n[{}/{}] Running: {} {} (1e{} bundles, elapse {} s)",
+                done,
+                total,
+                plan.path.display(),
+                plan.seed,
+                (config.bundle_max_count as f64).log10().round() as usize,
+                config.elapse_cap_s
+            );
+            let cp_file = plan.path.to_string_lossy().to_string();
+            if let Some(c) = checkpoint.as_ref() {
+                if c.borrow().plan_done(&cp_file, config, plan.seed) {
+                    println!("Already done, skipped.");
+                    continue;
+                }
+            }
+            ran += 1;
+            // a broken plan must not stop the grid, A-SABR panics on some of them
+            let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+                plan.prepare()
+                    .and_then(|_| run_plan(&cp_file, plan.seed, config, checkpoint.as_ref()))
+            }));
+            let error = match outcome {
+                Ok(Ok(_)) => None,
+                Ok(Err(e)) => Some(e),
+                Err(_) => Some("panicked".to_string()),
+            };
+            if let Some(e) = error {
+                println!("ERR: {}: {}", cp_file, e);
+                failed.push(cp_file);
+            }
+            let elapsed = started.elapsed();
+            let eta = elapsed.mul_f64((total - done) as f64 / ran as f64);
+            println!(
+                "{}, Progress {}/{} ({:.1}%), elapsed {}, ETA {}",
+                time_now(),
+                done,
+                total,
+                done as f64 * 100.0 / total as f64,
+                hms(elapsed),
+                hms(eta)
+            );
+        }
+    }
+    println!(
+        "\n{}, Grid finished in {}: {} runs, {} failed, {} plans missing.",
+        time_now(),
+        hms(started.elapsed()),
+        total,
+        failed.len(),
+        missing.len()
+    );
+    for cp_file in failed.iter() {
+        println!("    failed: {}", cp_file);
+    }
+    Ok(())
+}
+
+pub fn hms(d: Duration) -> String {
+    let secs = d.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, (secs % 3600) / 60, secs % 60)
+}
@@ -0,0 +1,227 @@
+//! Importer for the legacy grid-search logs (grid-search/log/*.log): UTF-16 console output of run_asabr.bat
+//! piped to Tee-Object, with the parameters only in the file name.
+//! * `(elapse 3s)`: elapse cap of 3 s with the throttle on, 1e5 bundles at most.
+//! * `(6 2e4 b)`: 6 routers, 2e4 bundles, throttle off (the elapse cap is then unused and left to its default).
+//!
+//! The other parameters were hard coded in main.rs at the time and are the defaults of EvalConfig.
+//! The plans come from generate_cp.sh with ptvg seed 1, the second argument of the legacy runs is only
+//! a memo of run_asabr.bat and is dropped.
+//! The QDManager and SegmentationManager blocks are skipped: the plan was always parsed with EVLManager
+//! at the time, they are EVL measurements under another label (see the README).
+use std::{fs, path::Path};
+
+use crate::{
+    checkpoint::write_atomic,
+    config::EvalConfig,
+    results::{Metrics, RunRecord, SpsnRecord},
+    traffic::{LifetimeConfig, TrafficConfig},
+};
+
+/// Parameters recovered from a log file name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogParams {
+    pub elapse_cap_s: f64,
+    pub throttle_on: bool,
+    pub bundle_max_count: usize,
+}
+
+pub fn params_from_file_name(name: &str, defaults: &EvalConfig) -> Result<LogParams, String> {
+    let open = name.rfind('(').ok_or_else(|| format!("No (parameters) in {}", name))?;
+    let close = name[open..]
+        .find(')')
+        .ok_or_else(|| format!("No (parameters) in {}", name))?;
+    let inner: Vec<&str> = name[open + 1..open + close].split_whitespace().collect();
+    match inner.as_slice() {
+        ["elapse", secs] => Ok(LogParams {
+            elapse_cap_s: secs
+                .trim_end_matches('s')
+                .parse()
+                .map_err(|_| format!("Invalid elapse cap in {}", name))?,
+            throttle_on: true,
+            bundle_max_count: defaults.bundle_max_count,
+        }),
+        [_routers, count, "b"] => Ok(LogParams {
+            elapse_cap_s: defaults.elapse_cap_s,
+            throttle_on: false,
+            bundle_max_count: count
+                .parse::<f64>()
+                .map_err(|_| format!("Invalid bundle count in {}", name))?
+                as usize,
+        }),
+        _ => Err(format!("Unknown parameters ({}) in {}", inner.join(" "), name)),
+    }
+}
+
+/// UTF-16 (with BOM) as written by PowerShell, UTF-8 otherwise.
+pub fn decode_log(bytes: &[u8]) -> String {
+    let utf16 = |le: bool| {
+        let units: Vec<u16> = bytes[2..]
+            .chunks_exact(2)
+            .map(|c| if le { u16::from_le_bytes([c[0], c[1]]) } else { u16::from_be_bytes([c[0], c[1]]) })
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+    match bytes {
+        [0xFF, 0xFE, ..] => utf16(true),
+        [0xFE, 0xFF, ..] => utf16(false),
+        _ => String::from_utf8_lossy(bytes).to_string(),
+    }
+}
+
+/// Statistics printed by main.rs for the plan being measured.
+#[derive(Debug, Default, Clone)]
+struct PlanStats {
+    plan: String,
+    nodes: u16,
+    contacts: usize,
+    avg_volume: f64,
+    start_time: f64,
+    end_time: f64,
+}
+
+/// tvgutil seed of the plans generated by grid-search/generate_cp.sh.
+const LEGACY_PTVG_SEED: u64 = 1;
+/// The logs sized the bundles with this rate for every contact.
+const LEGACY_DATA_RATE: f64 = 9600.0;
+
+/// `SpsnHybridParenting   :mean=    30835 ns,std=    47463 ns,failure rate=  49.22%,...,max=   0.69 ms.`
+fn parse_metric_line(line: &str) -> Option<(String, Metrics)> {
+    let (name, rest) = line.split_once(':')?;
+    let value = |key: &str| -> Option<f64> {
+        rest.trim_end_matches('.')
+            .split(',')
+            .filter_map(|kv| kv.split_once('='))
+            .find(|(k, _)| k.trim() == key)
+            .and_then(|(_, v)| v.split_whitespace().next())
+            .and_then(|v| v.trim_end_matches('%').parse::<f64>().ok())
+    };
+    let metrics = Metrics {
+        mean_ns: value("mean")?,
+        std_ns: value("std")?,
+        fail_rate: value("failure rate")? / 100.0,
+        expired_rate: None,
+        sch_rate: value("schedule rate")?,
+        sum_s: value("total time")?,
+        p0: value("min")?,
+        p5: None,
+        p10: None,
+        p20: None,
+        p50: None,
+        p80: None,
+        p90: None,
+        p95: None,
+        p100: value("max")? * 1e6,
+    };
+    Some((name.trim().to_string(), metrics))
+}
+
+/// Records of a log, and the number of metric lines skipped because their contact manager was not measured.
+pub fn import_log(path: &Path, defaults: &EvalConfig) -> Result<(Vec<RunRecord>, usize), String> {
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let params = params_from_file_name(&file_name, defaults)?;
+    let bytes = fs::read(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let text = decode_log(&bytes);
+
+    let mut records = Vec::new();
+    let mut skipped = 0;
+    let mut stats = PlanStats::default();
+    let mut cm_label: Option<String> = None;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        let at = |msg: &str| format!("{}:{}: {}", path.display(), i + 1, msg);
+        if let Some(rest) = line.strip_prefix("Running: ") {
+            // Running: ..\a_sabr "02_ptvg_18_24h.json" 2
+            stats = PlanStats {
+                plan: rest.split('"').nth(1).unwrap_or_default().to_string(),
+                ..PlanStats::default()
+            };
+            cm_label = None;
+        } else if let Some(pos) = line.find("Working with cp ") {
+            let plan = line[pos + "Working with cp ".len()..].trim_end_matches([',', ' ']);
+            stats.plan = plan.to_string();
+        } else if let Some(rest) = line.trim_start().strip_prefix("which contains ") {
+            // which contains 18 nodes, 1060 contacts with an average 6429934.78 contact volume,
+            let words: Vec<&str> = rest.split_whitespace().collect();
+            stats.nodes = words.first().and_then(|w| w.parse().ok()).ok_or_else(|| at("invalid node count"))?;
+            stats.contacts = words.get(2).and_then(|w| w.parse().ok()).ok_or_else(|| at("invalid contact count"))?;
+            stats.avg_volume = words.get(7).and_then(|w| w.parse().ok()).ok_or_else(|| at("invalid average volume"))?;
+        } else if let Some(rest) = line.trim_start().strip_prefix("first contact at ") {
+            // first contact at 1752098544.1562054, last contact at 1752184515.230837.
+            let (first, last) = rest.split_once(", last contact at ").ok_or_else(|| at("invalid dates"))?;
+            stats.start_time = first.parse().map_err(|_| at("invalid first contact"))?;
+            stats.end_time = last.trim_end_matches('.').parse().map_err(|_| at("invalid last contact"))?;
+        } else if let Some(pos) = line.find("Measuring compute time for all routers with ") {
+            cm_label = Some(line[pos + "Measuring compute time for all routers with ".len()..].trim().to_string());
+        } else if line.contains(":mean=") {
+            let (router, metrics) = parse_metric_line(line).ok_or_else(|| at("invalid metrics"))?;
+            let cm = cm_label.clone().ok_or_else(|| at("metrics before any contact manager"))?;
+            if cm != "EVLManager" {
+                skipped += 1;
+                continue;
+            }
+            let spsn = router.starts_with("Spsn") || router.starts_with("VolCgr");
+            records.push(RunRecord {
+                source: format!("legacy:{}", file_name),
+                plan: stats.plan.clone(),
+                nodes: stats.nodes,
+                contacts: stats.contacts,
+                start_time: stats.start_time,
+                end_time: stats.end_time,
+                horizon_s: stats.end_time - stats.start_time,
+                contact_manager: cm,
+                router,
+                spsn_options: spsn.then_some(SpsnRecord {
+                    check_size: defaults.spsn.check_size,
+                    check_priority: defaults.spsn.check_priority,
+                    max_entries: defaults.spsn.max_entries,
+                }),
+                data_rate: defaults.data_rate.unwrap_or(LEGACY_DATA_RATE),
+                avg_contact_volume: stats.avg_volume,
+                bundle_max_count: params.bundle_max_count,
+                // without throttle every bundle is routed
+                bundle_count: (!params.throttle_on).then_some(params.bundle_max_count),
+                bundle_size_min_ratio: defaults.bundle_size_min_ratio,
+                bundle_size_max_ratio: defaults.bundle_size_max_ratio,
+                bundle_min_size: stats.avg_volume * defaults.bundle_size_min_ratio,
+                bundle_max_size: stats.avg_volume * defaults.bundle_size_max_ratio,
+                elapse_cap_s: params.elapse_cap_s,
+                throttle_on: params.throttle_on,
+                tvgutil_seed: LEGACY_PTVG_SEED,
+                bundle_seed_first: 1,
+                // the logs predate the traffic models, every bundle was routed at the first contact
+                traffic: TrafficConfig::default(),
+                lifetime: LifetimeConfig::default(),
+                priority_mix: Vec::new(),
+                metrics,
+                priority_metrics: Vec::new(),
+            });
+        }
+    }
+    Ok((records, skipped))
+}
+
+/// import-logs subcommand: converts the logs into one JSON Lines file, replaced atomically.
+pub fn run_import(out: &str, logs: &[String]) -> Result<(), String> {
+    let defaults = EvalConfig::default();
+    let mut contents = String::new();
+    let mut total = 0;
+    for log in logs.iter() {
+        match import_log(Path::new(log), &defaults) {
+            Ok((records, skipped)) => {
+                println!("{}: {} records, {} QDManager/SegmentationManager (EVL) lines skipped", log, records.len(), skipped);
+                total += records.len();
+                for record in records.iter() {
+                    contents.push_str(&serde_json::to_string(record).map_err(|e| e.to_string())?);
+                    contents.push('\n');
+                }
+            }
+            Err(e) => println!("ERR: {}", e),
+        }
+    }
+    write_atomic(Path::new(out), &contents)?;
+    println!("{} records from {} logs written to {}", total, logs.len(), out);
+    Ok(())
+}
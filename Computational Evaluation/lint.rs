@@ -0,0 +1,288 @@
+//! Contact plan validation, for the A-SABR text format (with `file:line` locations) and tvgutil JSON
+//! (with `edges[key][i]` locations), before a bad plan makes a parser or a router panic.
+//! Errors make the plan unusable or wrong:
+//! * contacts with end ≤ start, a rate ≤ 0 or a negative delay,
+//! * nodes that are not declared (`node` lines, `vertices`),
+//! * MAVs larger than the contact capacity (rate * duration),
+//! * A-SABR: a manager marker after `<rate> <delay>`, ASABRContactPlan reads it right after `<end>`,
+//! * tvgutil: an edge key that does not match the contact, characteristics out of order or outside the contact.
+//!
+//! Warnings are suspicious: overlapping contacts of the same pair, MAVs that increase with the priority
+//! level (p0 should be allowed the most), contacts outside `--horizon`.
+use std::{collections::BTreeMap, fmt, fs, path::Path};
+
+use crate::{
+    config::split_flags,
+    convert::read_plan,
+    plan::{edge_key, TvgPlan},
+};
+
+pub const LINT_USAGE: &str = "lint [options] <plan>...
+    --horizon <start,end>     expected time range of the contacts";
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Issue {
+    pub severity: Severity,
+    /// `file:line` or `file: edges[key][i]`
+    pub at: String,
+    pub message: String,
+    /// Line or contact index, to report in file order.
+    order: usize,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: {}: {}", self.at, severity, self.message)
+    }
+}
+
+/// A contact of either format, once parsed.
+struct Contact {
+    order: usize,
+    at: String,
+    from: String,
+    to: String,
+    start: f64,
+    end: f64,
+    /// Lowest rate of the contact, for its capacity.
+    rate: f64,
+    mavs: Vec<f64>,
+}
+
+#[derive(Default)]
+struct Lint {
+    issues: Vec<Issue>,
+}
+
+impl Lint {
+    fn error(&mut self, order: usize, at: &str, message: String) {
+        self.issues.push(Issue {
+            severity: Severity::Error,
+            at: at.to_string(),
+            message,
+            order,
+        });
+    }
+
+    fn warning(&mut self, order: usize, at: &str, message: String) {
+        self.issues.push(Issue {
+            severity: Severity::Warning,
+            at: at.to_string(),
+            message,
+            order,
+        });
+    }
+
+    /// The checks common to both formats.
+    fn check_contacts(&mut self, contacts: &[Contact], nodes: &[String], horizon: Option<(f64, f64)>) {
+        for c in contacts.iter() {
+            if c.end <= c.start {
+                self.error(c.order, &c.at, format!("end {} ≤ start {}", c.end, c.start));
+            }
+            if c.rate <= 0.0 {
+                self.error(c.order, &c.at, format!("rate {} ≤ 0", c.rate));
+            }
+            for node in [&c.from, &c.to] {
+                if !nodes.contains(node) {
+                    self.error(c.order, &c.at, format!("node {} is not declared", node));
+                }
+            }
+            let capacity = c.rate * (c.end - c.start);
+            for (p, mav) in c.mavs.iter().enumerate().filter(|_| capacity > 0.0) {
+                if *mav > capacity {
+                    self.error(c.order, &c.at, format!("MAV of priority {} ({}) > contact capacity ({})", p, mav, capacity));
+                }
+            }
+            if c.mavs.windows(2).any(|w| w[1] > w[0]) {
+                let mavs: Vec<String> = c.mavs.iter().map(|m| m.to_string()).collect();
+                self.warning(c.order, &c.at, format!("MAVs increase with the priority level ({})", mavs.join(" ")));
+            }
+            if let Some((start, end)) = horizon {
+                if c.start < start || c.end > end {
+                    self.warning(c.order, &c.at, format!("[{}, {}] is outside the horizon [{}, {}]", c.start, c.end, start, end));
+                }
+            }
+        }
+        // overlaps, per (from, to) in time order
+        let mut pairs: BTreeMap<(&str, &str), Vec<&Contact>> = BTreeMap::new();
+        for c in contacts.iter() {
+            pairs.entry((&c.from, &c.to)).or_default().push(c);
+        }
+        for same_pair in pairs.values_mut() {
+            same_pair.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap());
+            for w in same_pair.windows(2) {
+                if w[1].start < w[0].end {
+                    self.warning(w[1].order, &w[1].at, format!("overlaps the contact at {} ([{}, {}])", w[0].at, w[0].start, w[0].end));
+                }
+            }
+        }
+    }
+}
+
+fn lint_asabr(text: &str, path: &str, horizon: Option<(f64, f64)>) -> Vec<Issue> {
+    let mut lint = Lint::default();
+    let mut nodes: Vec<String> = Vec::new();
+    let mut contacts: Vec<Contact> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let at = format!("{}:{}", path, i + 1);
+        let tokens: Vec<&str> = line.split('#').next().unwrap_or("").split_whitespace().collect();
+        let num = |t: &str| t.parse::<f64>().ok();
+        match tokens.first() {
+            Some(&"node") => match tokens.get(1) {
+                Some(id) if id.parse::<usize>().is_ok() => {
+                    if nodes.iter().any(|n| n == id) {
+                        lint.error(i, &at, format!("node {} declared twice", id));
+                    }
+                    nodes.push(id.to_string());
+                }
+                _ => lint.error(i, &at, "expected `node <id> [name]`".to_string()),
+            },
+            Some(&"contact") => {
+                let header: Option<Vec<f64>> = tokens.get(3..5).map(|t| t.iter().filter_map(|v| num(v)).collect());
+                let (from, to) = (tokens.get(1), tokens.get(2));
+                let (Some(from), Some(to), Some([start, end])) = (from, to, header.as_deref()) else {
+                    lint.error(i, &at, "expected `contact <from> <to> <start> <end> ...`".to_string());
+                    continue;
+                };
+                let rest = &tokens[5..];
+                let marker = rest.iter().position(|t| num(t).is_none());
+                if marker.is_some_and(|m| m > 0) {
+                    lint.error(i, &at, format!("manager marker `{}` after <rate> <delay>, it goes right after <end>", rest[marker.unwrap()]));
+                }
+                if rest.iter().filter(|t| num(t).is_none()).count() > 1 {
+                    lint.error(i, &at, "more than one manager marker".to_string());
+                    continue;
+                }
+                let values: Vec<f64> = rest.iter().filter_map(|t| num(t)).collect();
+                let [rate, delay, mavs @ ..] = values.as_slice() else {
+                    lint.error(i, &at, "missing <rate> or <delay>".to_string());
+                    continue;
+                };
+                if *delay < 0.0 {
+                    lint.error(i, &at, format!("delay {} < 0", delay));
+                }
+                contacts.push(Contact {
+                    order: i,
+                    at,
+                    from: from.to_string(),
+                    to: to.to_string(),
+                    start: *start,
+                    end: *end,
+                    rate: *rate,
+                    mavs: mavs.to_vec(),
+                });
+            }
+            Some(other) => lint.error(i, &at, format!("unknown line `{}`", other)),
+            None => {}
+        }
+    }
+    lint.check_contacts(&contacts, &nodes, horizon);
+    lint.issues
+}
+
+fn lint_tvgutil(plan: &TvgPlan, path: &str, horizon: Option<(f64, f64)>) -> Vec<Issue> {
+    let mut lint = Lint::default();
+    let mut contacts: Vec<Contact> = Vec::new();
+    for (key, edge) in plan.edges.iter() {
+        for (i, c) in edge.iter().enumerate() {
+            let at = format!("{}: edges[{}][{}]", path, key, i);
+            let order = contacts.len() + 1; // 0 is for the vertices
+            if edge_key(&c.tx_node, &c.rx_node) != *key {
+                lint.error(order, &at, format!("contact from {} to {} under the edge {}", c.tx_node, c.rx_node, key));
+            }
+            if c.characteristics.is_empty() {
+                lint.error(order, &at, "no characteristics".to_string());
+            }
+            for (j, ch) in c.characteristics.iter().enumerate() {
+                if ch.delay < 0.0 {
+                    lint.error(order, &at, format!("characteristics[{}]: delay {} < 0", j, ch.delay));
+                }
+                if c.start < c.end && (ch.starting_at < c.start || ch.starting_at >= c.end) {
+                    lint.error(order, &at, format!("characteristics[{}] starts at {}, outside [{}, {}]", j, ch.starting_at, c.start, c.end));
+                }
+                if j > 0 && ch.starting_at <= c.characteristics[j - 1].starting_at {
+                    lint.error(order, &at, format!("characteristics[{}] is not after characteristics[{}]", j, j - 1));
+                }
+            }
+            contacts.push(Contact {
+                order,
+                at,
+                from: c.tx_node.clone(),
+                to: c.rx_node.clone(),
+                start: c.start,
+                end: c.end,
+                rate: c.characteristics.iter().map(|ch| ch.bit_rate).fold(f64::INFINITY, f64::min),
+                mavs: Vec::new(),
+            });
+        }
+    }
+    let mut seen: Vec<&String> = Vec::new();
+    for v in plan.vertices.iter() {
+        if seen.contains(&v) {
+            lint.error(0, &format!("{}: vertices", path), format!("vertex {} declared twice", v));
+        }
+        seen.push(v);
+    }
+    lint.check_contacts(&contacts, &plan.vertices, horizon);
+    lint.issues
+}
+
+/// Issues of a plan file, errors first, in file order.
+pub fn lint_file(path: &Path, horizon: Option<(f64, f64)>) -> Result<Vec<Issue>, String> {
+    let name = path.to_string_lossy();
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", name, e))?;
+    if text.lines().any(|l| l.split_whitespace().take(2).eq(["a", "contact"])) {
+        return Err(format!("{}: ION plans are not linted, convert them first", name));
+    }
+    let mut issues = if text.trim_start().starts_with('{') {
+        let (plan, _) = read_plan(path)?;
+        lint_tvgutil(&plan, &name, horizon)
+    } else {
+        lint_asabr(&text, &name, horizon)
+    };
+    issues.sort_by(|a, b| b.severity.partial_cmp(&a.severity).unwrap().then(a.order.cmp(&b.order)));
+    Ok(issues)
+}
+
+/// lint subcommand: `lint [options] <plan>...`
+pub fn run_lint(args: &[String]) -> Result<(), String> {
+    let (flags, positional) = split_flags(args)?;
+    let mut horizon: Option<(f64, f64)> = None;
+    for (flag, value) in flags.iter() {
+        match flag.as_str() {
+            "--horizon" => {
+                let invalid = || format!("Invalid value for --horizon: {}", value);
+                let (start, end) = value.split_once(',').ok_or_else(invalid)?;
+                horizon = Some((start.trim().parse().map_err(|_| invalid())?, end.trim().parse().map_err(|_| invalid())?));
+            }
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+    if positional.is_empty() {
+        return Err("No plan".to_string());
+    }
+    let mut errors = 0;
+    for file in positional.iter() {
+        let issues = lint_file(Path::new(file), horizon)?;
+        for issue in issues.iter() {
+            println!("{}", issue);
+        }
+        let file_errors = issues.iter().filter(|i| i.severity == Severity::Error).count();
+        println!("{}: {} errors, {} warnings", file, file_errors, issues.len() - file_errors);
+        errors += file_errors;
+    }
+    if errors > 0 {
+        return Err(format!("{} errors", errors));
+    }
+    Ok(())
+}
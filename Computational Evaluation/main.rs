@@ -4,19 +4,44 @@ use a_sabr::contact;
 use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::{
     cell::RefCell,
+    collections::HashMap,
     env,
-    fs::File,
+    fs::{self, File},
     io::Write,
+    path::PathBuf,
     rc::Rc,
     time::{Duration, Instant, SystemTime},
 };
 
+mod checkpoint;
+mod config;
+mod convert;
+mod edges;
+mod grid;
+mod legacy;
+mod lint;
+mod plan;
+mod relay;
+mod report;
+mod results;
+mod ringroad;
+mod slice;
+mod stats;
+mod subset;
+mod traffic;
+
+use checkpoint::{write_atomic, Checkpoint};
+use config::{EvalConfig, FLAGS_USAGE};
+use results::{append_record, ratio, records_path, Metrics, PriorityMetrics, RunRecord, SpsnRecord};
+use traffic::{LifetimeConfig, LifetimeModel, TrafficConfig};
+
 use a_sabr::{
     bundle::Bundle,
     contact_manager::{
-        legacy::evl::EVLManager, legacy::qd::QDManager, seg::SegmentationManager, ContactManager,
+        legacy::evl::EVLManager, legacy::qd::QDManager, myevl::EVLManager as PriorityEVLManager,
+        seg::SegmentationManager, ContactManager,
     },
-    contact_plan::from_tvgutil_file::TVGUtilContactPlan,
+    contact_plan::from_tvgutil_file::{FromTVGUtilContactData, TVGUtilContactPlan},
     node_manager::none::NoManagement,
     route_storage::{cache::TreeCache, table::RoutingTable},
     routing::{
@@ -26,7 +51,7 @@ use a_sabr::{
 };
 
 // Returns the current UTC time in HH:MM:SS format
-fn time_now() -> String {
+pub fn time_now() -> String {
     let sec_since_epoch = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .expect("System time before UNIX EPOCH!")
@@ -38,7 +63,7 @@ fn time_now() -> String {
     format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
 }
 
-fn percentile(sorted: &Vec<f64>, p: f64) -> f64 {
+pub fn percentile(sorted: &Vec<f64>, p: f64) -> f64 {
     let n = sorted.len();
     if n == 0 {
         return 0.0;
@@ -70,6 +95,25 @@ where
     (elapsed, is_success)
 }
 
+/// Draws a priority from the weights of the priorities 0, 1, 2..., 1 without weights.
+fn draw_priority(priority_mix: &[f64], rng: &mut StdRng) -> u8 {
+    if priority_mix.is_empty() {
+        return 1;
+    }
+    let mut draw = rng.random_range(0.0..priority_mix.iter().sum::<f64>());
+    for (p, w) in priority_mix.iter().enumerate() {
+        if draw < *w {
+            return p as u8;
+        }
+        draw -= w;
+    }
+    (priority_mix.len() - 1) as u8
+}
+
+/// Also returns the (priority, success, expired) of every routed bundle, for the per-priority metrics.
+/// With `segments`, a failed bundle is expired when a path of the plan reaches its destination by the end
+/// of the plan but none before its expiration, whatever the capacity. The failed bundles are classified
+/// after the timed loop, so that the search does not use the elapse cap.
 fn batch_compute_times<NM, CM>(
     routers: &mut [Box<dyn Router<NM, CM>>],
     node_count: u16,
@@ -78,22 +122,33 @@ fn batch_compute_times<NM, CM>(
     bundle_max_size: f64,
     start_time: f64,
     end_time: f64,
+    traffic: &TrafficConfig,
+    priority_mix: &[f64],
+    lifetime: &LifetimeConfig,
+    segments: Option<&[Vec<(usize, f64, f64, f64)>]>,
     throttle_on: bool,
     elapse_cap: Duration,
-) -> (Vec<Vec<Duration>>, Vec<f64>, Vec<f64>)
+) -> (Vec<Vec<Duration>>, Vec<f64>, Vec<f64>, Vec<Vec<(u8, bool, bool)>>)
 where
     NM: a_sabr::node_manager::NodeManager + 'static,
     CM: a_sabr::contact_manager::ContactManager + 'static,
 {
     // create Vec<Duration> for each router to track routing durations
     let mut durations = vec![Vec::new(); routers.len()];
+    let mut outcomes = vec![Vec::new(); routers.len()];
     let mut bundle_schedule_rate: Vec<f64> = Vec::with_capacity(routers.len());
     let mut failure_rate: Vec<f64> = Vec::with_capacity(routers.len());
+    // the same creation times for every router, in time order
+    let creation_times = traffic.creation_times(bundle_max_count, start_time, end_time);
+    // earliest arrivals from (source, creation time), shared by the routers
+    let mut arrivals: HashMap<(u16, u64), Vec<f64>> = HashMap::new();
     for (router_idx, router_box) in routers.iter_mut().enumerate() {
         let router_ref: &mut dyn Router<NM, CM> = router_box.as_mut();
         let mut elapse: Duration = Duration::new(0, 0);
         let mut failure_count = 0.0;
         let mut bundle_count = bundle_max_count;
+        // (bundle, source, destination, expiration) of the failed bundles
+        let mut failed: Vec<(usize, u16, u16, f64)> = Vec::new();
         for i in 0..bundle_max_count {
             if elapse > elapse_cap && throttle_on {
                 bundle_count = i;
@@ -107,52 +162,113 @@ where
             while dst == src {
                 dst = rng.random_range(0..node_count);
             }
+            // drawn last, the bundles keep the same size and nodes with or without a mix
+            let priority = draw_priority(priority_mix, &mut rng);
+            let expiration = lifetime.expiration(creation_times[i], end_time, &mut rng);
             let bundle = Bundle {
                 source: src,
                 destinations: vec![dst], // unicast
-                priority: 1,
+                priority,
                 size,
-                expiration: end_time,
+                expiration,
             };
-            let (d, is_success) = run_time(router_ref, &bundle, start_time);
+            let (d, is_success) = run_time(router_ref, &bundle, creation_times[i]);
             elapse += d;
             if !is_success {
                 failure_count += 1.0;
+                failed.push((i, src, dst, expiration));
             };
             durations[router_idx].push(d);
+            outcomes[router_idx].push((priority, is_success, false));
         }
-        bundle_schedule_rate.push((bundle_count as f64 + 1.0) / (elapse.as_nanos() as f64) / 1e-9); // (i+1) as f64 / durations[router_idx].iter().sum::<Duration>().as_nanos() as f64 / 1e-9,
-        failure_rate.push(failure_count / (bundle_count as f64 + 1.0));
+        // reachable in the plan, but not before the expiration
+        if let Some(out) = segments {
+            for (i, src, dst, expiration) in failed {
+                let arrival = arrivals
+                    .entry((src, creation_times[i].to_bits()))
+                    .or_insert_with(|| stats::earliest_arrivals(out, src as usize, creation_times[i], end_time))[dst as usize];
+                outcomes[router_idx][i].2 = arrival <= end_time && arrival > expiration;
+            }
+        }
+        bundle_schedule_rate.push(ratio(bundle_count as f64 + 1.0, elapse.as_secs_f64())); // (i+1) as f64 / durations[router_idx].iter().sum::<Duration>().as_nanos() as f64 / 1e-9,
+        failure_rate.push(ratio(failure_count, bundle_count as f64 + 1.0));
     }
-    (durations, bundle_schedule_rate, failure_rate)
+    (durations, bundle_schedule_rate, failure_rate, outcomes)
 }
 
-/// CM（EVL, QD, Seg）measure and print
-fn measure_for<CM>(
-    cm_label: &str,
-    router_configs: &Vec<(&'static str, Option<SpsnOptions>)>,
-    router_names: &Vec<&'static str>,
-    cp_file: &str,
+/// Parameters shared by the measurements of every contact manager
+struct MeasureSetup<'a> {
+    config: &'a EvalConfig,
+    router_configs: &'a Vec<(String, Option<SpsnOptions>)>,
+    cp_file: &'a str,
     node_count: u16,
     contact_count: usize,
-    bundle_max_count: usize,
+    /// Average rate of the contacts, or the fixed one of the config.
+    data_rate: f64,
+    avg_contact_volume: f64,
     bundle_min_size: f64,
     bundle_max_size: f64,
     start_time: f64,
     end_time: f64,
-    elapse_cap: Duration,
-    throttle_on: bool,
-    export_csv: bool,
+    /// Contact segments of the plan, to tell expired bundles from capacity failures.
+    segments: &'a [Vec<(usize, f64, f64, f64)>],
     tvgutil_seed: u64,
-) where
-    CM: ContactManager + 'static,
+    /// Set by the grid search to skip and record finished routers.
+    checkpoint: Option<&'a RefCell<Checkpoint>>,
+}
+
+/// Dispatch a contact manager label to measure_for with the matching type
+fn measure_cm(cm_label: &str, setup: &MeasureSetup) -> Vec<RunRecord> {
+    match cm_label {
+        "EVLManager" => measure_for::<EVLManager>(cm_label, setup),
+        "QDManager" => measure_for::<QDManager>(cm_label, setup),
+        "SegmentationManager" => measure_for::<SegmentationManager>(cm_label, setup),
+        "PriorityEVLManager" => measure_for::<PriorityEVLManager>(cm_label, setup),
+        _ => {
+            eprintln!("ERR: Unknown contact manager: {}", cm_label);
+            Vec::new()
+        }
+    }
+}
+
+/// CM（EVL, QD, Seg, priority EVL）measure and print
+/// Routers are measured one after another, so that each one is checkpointed as soon as it is done.
+/// Returns the records of the routers measured, the ones skipped by the checkpoint are not included.
+fn measure_for<CM>(cm_label: &str, setup: &MeasureSetup) -> Vec<RunRecord>
+where
+    CM: ContactManager + FromTVGUtilContactData<NoManagement, CM> + 'static,
 {
-    let mut routers_box: Vec<Box<dyn Router<NoManagement, EVLManager>>> = Vec::new();
-    for (name, options) in router_configs.into_iter() {
-        let (nodes, contacts) = TVGUtilContactPlan::parse::<NoManagement, EVLManager>(cp_file)
-            .expect("!!!Failed to parse contact plan");
-        let router = build_generic_router(name, nodes, contacts, options.clone());
-        routers_box.push(router);
+    let MeasureSetup {
+        config,
+        router_configs,
+        cp_file,
+        node_count,
+        contact_count,
+        data_rate,
+        avg_contact_volume,
+        bundle_min_size,
+        bundle_max_size,
+        start_time,
+        end_time,
+        segments,
+        tvgutil_seed,
+        checkpoint,
+    } = *setup;
+    let bundle_max_count = config.bundle_max_count;
+    let elapse_cap = config.elapse_cap();
+    let key = |name: &str| Checkpoint::key(cp_file, cm_label, name, config, tvgutil_seed);
+    let pending: Vec<&(String, Option<SpsnOptions>)> = router_configs
+        .iter()
+        .filter(|(name, _)| checkpoint.map_or(true, |c| !c.borrow().is_done(&key(name))))
+        .collect();
+    if pending.is_empty() {
+        println!(
+            "{}, Skipping {} with {}: all routers already done",
+            time_now(),
+            cp_file,
+            cm_label
+        );
+        return Vec::new();
     }
 
     println!(
@@ -160,101 +276,252 @@ fn measure_for<CM>(
         time_now(),
         cm_label
     );
-    let (compute_times, schedule_rate, failure_rate) = batch_compute_times(
-        &mut routers_box,
+    // export CSV: metrics rows, algos columns like print
+    let csv_path = PathBuf::from(format!(
+        "{}/{}_{}_{}_1e{}b_{}s_{}{}.csv",
+        config.results_dir,
         node_count,
-        bundle_max_count,
-        bundle_min_size,
-        bundle_max_size,
-        start_time,
-        end_time,
-        throttle_on,
-        elapse_cap,
+        contact_count,
+        cm_label,
+        (bundle_max_count as f64).log10().round() as usize,
+        config.elapse_cap_s,
+        tvgutil_seed,
+        config.workload_suffix()
+    ));
+    let csv_header = format!(
+        "algo,mean_ns,std_ns,fail_rate,expired_rate,sch_rate,sum_s,p0,p5,p10,p20,p50,p80,p90,p95,p100,{}",
+        config.csv_header()
     );
-    // println!(
-    //     "{}, Finished measuring compute times for all routers with {}.",
-    //     time_now(),
-    //     cm_label
-    // );
-    // export CSV: metrics rows, algos columns like print
-    let mut file: Option<File> = None;
-    if export_csv {
-        let filename = format!(
-            "../results/{}_{}_{}_1e{}b_{}s_{}.csv",
+    let mut records: Vec<RunRecord> = Vec::new();
+    // (algo, row), the rows of the routers done before an interruption are kept
+    let mut csv_rows: Vec<(String, String)> = Vec::new();
+    if config.export_csv && checkpoint.is_some() {
+        if let Ok(text) = fs::read_to_string(&csv_path) {
+            for line in text.lines().skip(1) {
+                if let Some((algo, _)) = line.split_once(',') {
+                    csv_rows.push((algo.to_string(), line.to_string()));
+                }
+            }
+        }
+    }
+    for (name, options) in pending {
+        // every router gets its own copy of the contacts, managed by CM
+        let (nodes, contacts) = TVGUtilContactPlan::parse::<NoManagement, CM>(cp_file)
+            .expect("!!!Failed to parse contact plan");
+        let mut routers_box: Vec<Box<dyn Router<NoManagement, CM>>> =
+            vec![build_generic_router(name.as_str(), nodes, contacts, options.clone())];
+        let (compute_times, schedule_rate, failure_rate, outcomes) = batch_compute_times(
+            &mut routers_box,
             node_count,
-            contact_count,
-            cm_label,
-            (bundle_max_count as f64).log10().round() as usize,
-            elapse_cap.as_secs(),
-            tvgutil_seed
+            bundle_max_count,
+            bundle_min_size,
+            bundle_max_size,
+            start_time,
+            end_time,
+            &config.traffic,
+            &config.priority_mix,
+            &config.lifetime,
+            (config.lifetime.model != LifetimeModel::None).then_some(segments),
+            config.throttle_on,
+            elapse_cap,
         );
-        let f = File::create(&filename).expect("!Can not create CSV file");
-        // header
-        writeln!(
-            &f,
-            "algo,mean_ns,std_ns,fail_rate,sch_rate,sum_s,p0,p5,p10,p20,p50,p80,p90,p95,p100"
-        )
-        .unwrap();
-        file = Some(f); // move into file
-    }
-    for (i, name) in router_names.iter().enumerate() {
-        let times = &compute_times[i];
-        let sum_ns = times.iter().sum::<Duration>().as_nanos() as f64; // nanoseconds
-        let sum_s = sum_ns / 1e9; // seconds
-        let mut ns_vals: Vec<f64> = times.iter().map(|d| d.as_nanos() as f64).collect();
-        ns_vals.sort_by(|a, b| a.partial_cmp(b).unwrap());
-        let mean_ns = sum_ns / ns_vals.len() as f64;
-        let var_ns =
-            ns_vals.iter().map(|&v| (v - mean_ns).powi(2)).sum::<f64>() / ns_vals.len() as f64;
-        let std_ns = var_ns.sqrt();
-        let p0 = percentile(&ns_vals, 0.0); // ns
-        let p5 = percentile(&ns_vals, 5.0);
-        let p10 = percentile(&ns_vals, 10.0);
-        let p20 = percentile(&ns_vals, 20.0);
-        let p50 = percentile(&ns_vals, 50.0);
-        let p80 = percentile(&ns_vals, 80.0);
-        let p90 = percentile(&ns_vals, 90.0);
-        let p95 = percentile(&ns_vals, 95.0);
-        let p100 = percentile(&ns_vals, 100.0);
-        let sch_rate = schedule_rate[i];
-        let fail_rate = failure_rate[i];
+        let times = &compute_times[0];
+        let mut m = Metrics::from_times(times, schedule_rate[0], failure_rate[0]);
+        if config.lifetime.model != LifetimeModel::None {
+            // same denominator as the failure rate
+            let expired = outcomes[0].iter().filter(|(_, _, expired)| *expired).count();
+            m.expired_rate = Some(ratio(expired as f64, times.len() as f64 + 1.0));
+        }
         println!(
             "{:32}:mean= {:>8.0} ns,std= {:>8.0} ns,failure rate= {:>6.2}%,schedule rate= {:>9.2} bundles/sec,total time= {:>4.2} s,min= {:>6.0} ns,max= {:>6.2} ms.",
             name,
-            mean_ns,
-            std_ns,
-            fail_rate * 100.0,
-            sch_rate,
-            sum_s,
-            p0,
-            p100 / 1e6,
+            m.mean_ns,
+            m.std_ns,
+            m.fail_rate * 100.0,
+            m.sch_rate,
+            m.sum_s,
+            m.p0,
+            m.p100 / 1e6,
         );
-        if let Some(f) = file.as_mut() {
-            writeln!(
-                f,
-                "{},{:.0},{:.0},{:.4},{:.2},{:.2},{:.0},{:.0},{:.0},{:.0},{:.0},{:.0},{:.0},{:.0},{:.0}",
-                name, mean_ns, std_ns, fail_rate, sch_rate, sum_s,
-                p0, p5, p10, p20, p50, p80, p90, p95, p100,
-            )
-            .unwrap();
+        if let Some(expired_rate) = m.expired_rate {
+            println!(
+                "    expired= {:>6.2}%,other failures= {:>6.2}%.",
+                expired_rate * 100.0,
+                (m.fail_rate - expired_rate) * 100.0
+            );
+        }
+        // per priority, not in the CSV
+        let mut priority_metrics: Vec<PriorityMetrics> = Vec::new();
+        for priority in 0..config.priority_mix.len() as u8 {
+            let mut p_times: Vec<Duration> = Vec::new();
+            let (mut p_failures, mut p_expired) = (0.0, 0.0);
+            for (d, (p, is_success, expired)) in times.iter().zip(outcomes[0].iter()) {
+                if *p == priority {
+                    p_times.push(*d);
+                    if !is_success {
+                        p_failures += 1.0;
+                    }
+                    if *expired {
+                        p_expired += 1.0;
+                    }
+                }
+            }
+            if p_times.is_empty() {
+                continue;
+            }
+            let sum_s = p_times.iter().sum::<Duration>().as_secs_f64();
+            let mut pm = Metrics::from_times(&p_times, ratio(p_times.len() as f64, sum_s), ratio(p_failures, p_times.len() as f64));
+            pm.expired_rate = m.expired_rate.map(|_| ratio(p_expired, p_times.len() as f64));
+            println!(
+                "    priority {}: {:>7} bundles,failure rate= {:>6.2}%,expired= {:>6.2}%,mean= {:>8.0} ns,p50= {:>8.0} ns,max= {:>6.2} ms.",
+                priority,
+                p_times.len(),
+                pm.fail_rate * 100.0,
+                pm.expired_rate.unwrap_or(0.0) * 100.0,
+                pm.mean_ns,
+                pm.p50.unwrap_or(f64::NAN),
+                pm.p100 / 1e6,
+            );
+            priority_metrics.push(PriorityMetrics {
+                priority,
+                bundle_count: p_times.len(),
+                metrics: pm,
+            });
+        }
+        if config.export_csv {
+            let p = |v: Option<f64>| v.map_or(String::new(), |v| format!("{:.0}", v));
+            let row = format!(
+                "{},{:.0},{:.0},{:.4},{},{:.2},{:.2},{:.0},{},{},{},{},{},{},{},{:.0},{}",
+                name, m.mean_ns, m.std_ns, m.fail_rate, m.expired_rate.map_or(String::new(), |r| format!("{:.4}", r)), m.sch_rate, m.sum_s,
+                m.p0, p(m.p5), p(m.p10), p(m.p20), p(m.p50), p(m.p80), p(m.p90), p(m.p95), m.p100,
+                config.csv_values(),
+            );
+            csv_rows.retain(|(algo, _)| algo != name);
+            csv_rows.push((name.clone(), row));
+            // same order as the router list whatever the order they were measured in
+            csv_rows.sort_by_key(|(algo, _)| router_configs.iter().position(|(n, _)| n == algo));
+            let mut contents = csv_header.clone();
+            for (_, row) in csv_rows.iter() {
+                contents.push('\n');
+                contents.push_str(row);
+            }
+            contents.push('\n');
+            write_atomic(&csv_path, &contents).expect("!Can not write CSV file");
+        }
+        let record = RunRecord {
+            source: "run".to_string(),
+            plan: cp_file.to_string(),
+            nodes: node_count,
+            contacts: contact_count,
+            start_time,
+            end_time,
+            horizon_s: end_time - start_time,
+            contact_manager: cm_label.to_string(),
+            router: name.clone(),
+            spsn_options: options.as_ref().map(|o| SpsnRecord {
+                check_size: o.check_size,
+                check_priority: o.check_priority,
+                max_entries: o.max_entries,
+            }),
+            data_rate,
+            avg_contact_volume,
+            bundle_max_count,
+            bundle_count: Some(times.len()),
+            bundle_size_min_ratio: config.bundle_size_min_ratio,
+            bundle_size_max_ratio: config.bundle_size_max_ratio,
+            bundle_min_size,
+            bundle_max_size,
+            elapse_cap_s: config.elapse_cap_s,
+            throttle_on: config.throttle_on,
+            tvgutil_seed,
+            bundle_seed_first: 1,
+            traffic: config.traffic.clone(),
+            lifetime: config.lifetime.clone(),
+            priority_mix: config.priority_mix.clone(),
+            metrics: m,
+            priority_metrics,
+        };
+        if config.export_jsonl {
+            if let Err(e) = append_record(&records_path(&config.results_dir), &record) {
+                eprintln!("ERR: {}", e);
+            }
+        }
+        records.push(record);
+        if let Some(c) = checkpoint {
+            if let Err(e) = c.borrow_mut().mark_done(key(name)) {
+                eprintln!("ERR: {}", e);
+            }
         }
     }
     // println!("\n{}, Finished compute stats with {}.\n", time_now(), cm_label);
+    records
 }
 
 fn main() {
-    // manual input parameters
-    let data_rate = 9600.0; // field `rate` in `ContactManager` is private
-    let bundle_max_count = 1e5 as usize; // max number of bundles that a router will route
-    let bundle_size_min_ratio = 0.01;
-    let bundle_size_max_ratio = 0.1;
-    let elapse_cap: Duration = Duration::from_secs(4);
-    let throttle_on = true;
-    let export_csv = false;
-    // parse from file and get contact plan statistics
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    // the report and the plan tools have their own options
+    let tool: Option<(fn(&[String]) -> Result<(), String>, &str)> = match args.get(1).map(String::as_str) {
+        Some("report") => Some((report::run_report, report::REPORT_USAGE)),
+        Some("convert") => Some((convert::run_convert, convert::CONVERT_USAGE)),
+        Some("lint") => Some((lint::run_lint, lint::LINT_USAGE)),
+        Some("relay") => Some((relay::run_relay, relay::RELAY_USAGE)),
+        Some("ringroad") => Some((ringroad::run_ringroad, ringroad::RINGROAD_USAGE)),
+        Some("slice") => Some((slice::run_slice, slice::SLICE_USAGE)),
+        Some("stats") => Some((stats::run_stats, stats::STATS_USAGE)),
+        Some("subset") => Some((subset::run_subset, subset::SUBSET_USAGE)),
+        _ => None,
+    };
+    if let Some((run, usage)) = tool {
+        if let Err(e) = run(&args[2..]) {
+            eprintln!("ERR: {}\nUsage: {} {}", e, args[0], usage);
+            std::process::exit(1);
+        }
+        return;
+    }
+    // evaluation parameters: defaults, then --config <file.toml>, then flags
+    let config = EvalConfig::from_args(&mut args).unwrap_or_else(|e| {
+        eprintln!("ERR: {}", e);
+        std::process::exit(1);
+    });
+    if args.get(1).map(String::as_str) == Some("grid") {
+        if args.len() < 3 {
+            println!("Usage: {} grid [options] <grid.toml>\n{}", args[0], FLAGS_USAGE);
+            std::process::exit(1);
+        }
+        if let Err(e) = grid::run_grid(&args[2], &config) {
+            eprintln!("ERR: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.get(1).map(String::as_str) == Some("edges") {
+        if args.len() < 3 {
+            println!("Usage: {} edges [options] <grid.toml> [max failure % (0)]\n{}", args[0], FLAGS_USAGE);
+            std::process::exit(1);
+        }
+        let max_fail = args.get(3).map_or(Ok(0.0), |v| v.parse::<f64>()).unwrap_or_else(|_| {
+            eprintln!("ERR: Invalid failure rate: {}", args[3]);
+            std::process::exit(1);
+        });
+        if let Err(e) = edges::run_edges(&args[2], max_fail / 100.0, &config) {
+            eprintln!("ERR: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if args.get(1).map(String::as_str) == Some("import-logs") {
+        if args.len() < 4 {
+            println!("Usage: {} import-logs <out.jsonl> <grid-search log>...", args[0]);
+            std::process::exit(1);
+        }
+        if let Err(e) = legacy::run_import(&args[2], &args[3..]) {
+            eprintln!("ERR: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
     if args.len() < 3 {
-        println!("Usage: {} <cp_file> <seed for tvgutil>", args[0]);
+        println!("Usage: {} [options] <cp_file> <seed for tvgutil>\n       {} grid [options] <grid.toml>\n       {} convert <in> <out> [options]\n       {} edges [options] <grid.toml> [max failure %]\n       {} import-logs <out.jsonl> <grid-search log>...\n       {} report <elapse|bundles> [options] <records.jsonl>...\n       {} lint [options] <plan>...\n       {} relay [options] <out>\n       {} ringroad [options] <out.json>\n       {} slice <plan.json> <out_dir> (--hours <spec> | --windows <hours>)\n       {} stats [options] <plan>...\n       {} subset <plan.json> <out_dir> (--nodes <a,b,..> | --counts <spec>)\n{}", args[0], args[0], args[0], args[0], args[0], args[0], args[0], args[0], args[0], args[0], args[0], args[0], FLAGS_USAGE);
         std::process::exit(1);
     }
     let cp_file = &args[1];
@@ -262,13 +529,27 @@ fn main() {
         eprintln!("ERR: Invalid seed value: {}", args[2]);
         std::process::exit(1);
     });
+    if let Err(e) = run_plan(cp_file, tvgutil_seed, &config, None) {
+        eprintln!("ERR: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Parse a contact plan, print its statistics and measure all the routers with every contact manager of the config
+pub fn run_plan(
+    cp_file: &str,
+    tvgutil_seed: u64,
+    config: &EvalConfig,
+    checkpoint: Option<&RefCell<Checkpoint>>,
+) -> Result<Vec<RunRecord>, String> {
+    // parse from file and get contact plan statistics
     let (nodes_stat, contacts_stat) =
         TVGUtilContactPlan::parse::<NoManagement, EVLManager>(cp_file)
-            .expect("!!!Failed to parse contact plan");
+            .map_err(|e| format!("Failed to parse contact plan {}: {}", cp_file, e))?;
     let node_count = nodes_stat.len() as u16;
     let mut earliest_date = f64::MAX; // earliest contact start time to have bundles without expiration
     let mut latest_date = 0.0; // latest contact end time to have bundles without expiration
-    let mut total_volume = 0.0;
+    let mut total_duration = 0.0;
     let mut contact_count = 0;
     for contact in contacts_stat.iter() {
         if contact.info.start < earliest_date {
@@ -277,85 +558,59 @@ fn main() {
         if contact.info.end > latest_date {
             latest_date = contact.info.end;
         }
-        let duration = contact.info.end - contact.info.start;
-        // data_rate = contact.manager.rate; // field `rate` in `ContactManager` is private
-        total_volume += duration;
+        total_duration += contact.info.end - contact.info.start;
         contact_count += 1;
     }
-    let avg_volume = total_volume * data_rate / contact_count as f64;
-    let bundle_min_size = avg_volume * bundle_size_min_ratio;
-    let bundle_max_size = avg_volume * bundle_size_max_ratio;
-    println!("{}, Working with cp {}, \n    which contains {} nodes, {} contacts with an average {:.2} contact volume, \n    first contact at {}, last contact at {}.", time_now(), cp_file, node_count, contact_count, avg_volume, earliest_date, latest_date);
+    if contact_count == 0 {
+        return Err(format!("No contact in {}", cp_file));
+    }
+    // the rate of a contact is private to its ContactManager, the volumes come from the plan file
+    let tvg_plan = plan::TvgPlan::from_file(std::path::Path::new(cp_file))?;
+    let total_volume = match config.data_rate {
+        Some(rate) => total_duration * rate,
+        None => tvg_plan.contacts().map(|c| c.volume()).sum(),
+    };
+    let data_rate = total_volume / total_duration; // average over the contact time
+    let avg_volume = total_volume / contact_count as f64;
+    let bundle_min_size = avg_volume * config.bundle_size_min_ratio;
+    let bundle_max_size = avg_volume * config.bundle_size_max_ratio;
+    println!("{}, Working with cp {}, \n    which contains {} nodes, {} contacts with an average {:.2} contact volume ({} data rate {:.2}), \n    first contact at {}, last contact at {}.", time_now(), cp_file, node_count, contact_count, avg_volume, if config.data_rate.is_some() { "fixed" } else { "average" }, data_rate, earliest_date, latest_date);
+    println!("{}, Evaluation parameters:\n{}", time_now(), config);
+    if config.export_csv || config.export_jsonl {
+        fs::create_dir_all(&config.results_dir)
+            .map_err(|e| format!("{}: {}", config.results_dir, e))?;
+        // plan statistics next to the results, once per plan
+        let stats_file = stats::stats_path(std::path::Path::new(cp_file), Some(std::path::Path::new(&config.results_dir)));
+        if !stats_file.exists() {
+            let ground: Vec<String> = stats::DEFAULT_GROUND.iter().map(|p| p.to_string()).collect();
+            let stats = stats::plan_stats(cp_file, &tvg_plan, &ground, stats::DEFAULT_SAMPLES);
+            if let Err(e) = stats.write(&stats_file) {
+                eprintln!("ERR: {}", e);
+            }
+        }
+    }
+    let segments = stats::contact_segments(&tvg_plan);
     // generate routers
-    let spsn_options = Some(SpsnOptions {
-        check_size: true,
-        check_priority: false,
-        max_entries: 10,
-    });
-    let router_configs = vec![
-        ("SpsnHybridParenting", spsn_options.clone()),
-        ("SpsnNodeParenting", spsn_options.clone()),
-        ("SpsnContactParenting", spsn_options.clone()),
-        ("VolCgrHybridParenting", spsn_options.clone()),
-        ("VolCgrNodeParenting", spsn_options.clone()),
-        ("VolCgrContactParenting", spsn_options.clone()),
-        // ("CgrFirstEndingHybridParenting", None),
-        // ("CgrFirstEndingNodeParenting", None),
-        // ("CgrFirstEndingContactParenting", None),
-        // ("CgrFirstDepletedHybridParenting", None),
-        // ("CgrFirstDepletedNodeParenting", None),
-        // ("CgrFirstDepletedContactParenting", None),
-    ];
-    let router_names: Vec<&str> = router_configs.iter().map(|&(name, _)| name).collect();
-    measure_for::<EVLManager>(
-        "EVLManager",
-        &router_configs,
-        &router_names,
-        cp_file,
-        node_count,
-        contact_count,
-        bundle_max_count,
-        bundle_min_size,
-        bundle_max_size,
-        earliest_date,
-        latest_date,
-        elapse_cap,
-        throttle_on,
-        export_csv,
-        tvgutil_seed,
-    );
-    measure_for::<QDManager>(
-        "QDManager",
-        &router_configs,
-        &router_names,
-        cp_file,
-        node_count,
-        contact_count,
-        bundle_max_count,
-        bundle_min_size,
-        bundle_max_size,
-        earliest_date,
-        latest_date,
-        elapse_cap,
-        throttle_on,
-        export_csv,
-        tvgutil_seed,
-    );
-    measure_for::<SegmentationManager>(
-        "SegmentationManager",
-        &router_configs,
-        &router_names,
+    let router_configs = config.router_configs();
+    let setup = MeasureSetup {
+        config,
+        router_configs: &router_configs,
         cp_file,
         node_count,
         contact_count,
-        bundle_max_count,
+        data_rate,
+        avg_contact_volume: avg_volume,
         bundle_min_size,
         bundle_max_size,
-        earliest_date,
-        latest_date,
-        elapse_cap,
-        throttle_on,
-        export_csv,
+        start_time: earliest_date,
+        end_time: latest_date,
+        segments: &segments,
         tvgutil_seed,
-    );
+        checkpoint,
+    };
+    let mut records = Vec::new();
+    for cm_label in config.contact_managers.iter() {
+        records.extend(measure_cm(cm_label, &setup));
+    }
+    Ok(records)
 }
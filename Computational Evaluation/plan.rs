@@ -0,0 +1,340 @@
+//! tvgutil contact plans (`create_rr_tvg` JSON), read and written back for the plan tools.
+//! A-SABR only reads them through TVGUtilContactPlan, this model keeps the fields it does not use
+//! (`extra`) so that a plan is written back as tvgutil wrote it.
+//!
+//! ```json
+//! {"vertices": ["gs1", "sat1"],
+//!  "edges": {"[\"gs1\", \"sat1\"]": [{"tx_node": "gs1", "rx_node": "sat1", "start": 1751839200.0, "end": 1751839500.0,
+//!                                    "characteristics": [{"starting_at": 1751839200.0, "bit_rate": 9600.0, "delay": 0.01}]}]}}
+//! ```
+use std::{collections::BTreeMap, fs, path::Path};
+
+use a_sabr::{
+    contact_manager::{
+        legacy::evl::EVLManager, legacy::qd::QDManager, myevl::EVLManager as PriorityEVLManager, ContactManager,
+    },
+    contact_plan::{asabr_file_lexer::FileLexer, from_asabr_lexer::ASABRContactPlan, from_tvgutil_file::TVGUtilContactPlan},
+    node_manager::none::NoManagement,
+    parsing::{coerce_cm, ContactDispatcher, Dispatcher},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{checkpoint::write_atomic, time_now};
+
+/// Link characteristics from `starting_at` until the next one or the end of the contact.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Characteristic {
+    pub starting_at: f64,
+    pub bit_rate: f64,
+    pub delay: f64,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TvgContact {
+    pub tx_node: String,
+    pub rx_node: String,
+    pub start: f64,
+    pub end: f64,
+    pub characteristics: Vec<Characteristic>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl TvgContact {
+    /// One contact with a single characteristic, as tvgutil writes them.
+    pub fn new(tx: &str, rx: &str, start: f64, end: f64, bit_rate: f64, delay: f64) -> Self {
+        let mut extra = Map::new();
+        extra.insert("bit_error_rate".to_string(), Value::from(0.0));
+        TvgContact {
+            tx_node: tx.to_string(),
+            rx_node: rx.to_string(),
+            start,
+            end,
+            characteristics: vec![Characteristic {
+                starting_at: start,
+                bit_rate,
+                delay,
+                extra,
+            }],
+            extra: Map::new(),
+        }
+    }
+
+    /// The part of the contact in [start, end], None if it is outside.
+    pub fn clipped(&self, start: f64, end: f64) -> Option<TvgContact> {
+        let (s, e) = (self.start.max(start), self.end.min(end));
+        if s >= e {
+            return None;
+        }
+        // the characteristic in force at s becomes the first one
+        let first = self.characteristics.iter().rposition(|c| c.starting_at <= s).unwrap_or(0);
+        let mut characteristics: Vec<Characteristic> = self.characteristics[first..]
+            .iter()
+            .filter(|c| c.starting_at < e)
+            .cloned()
+            .collect();
+        if let Some(c) = characteristics.first_mut() {
+            c.starting_at = c.starting_at.max(s);
+        }
+        Some(TvgContact {
+            start: s,
+            end: e,
+            characteristics,
+            ..self.clone()
+        })
+    }
+
+    /// Volume at the characteristics rates.
+    pub fn volume(&self) -> f64 {
+        self.characteristics
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let until = self.characteristics.get(i + 1).map_or(self.end, |n| n.starting_at);
+                c.bit_rate * (until.min(self.end) - c.starting_at.max(self.start)).max(0.0)
+            })
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TvgPlan {
+    /// A-SABR numbers the nodes in this order.
+    pub vertices: Vec<String>,
+    pub edges: BTreeMap<String, Vec<TvgContact>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// Edge key as written by tvgutil, `json.dumps([tx, rx])`.
+pub fn edge_key(tx: &str, rx: &str) -> String {
+    format!("[{}, {}]", Value::from(tx), Value::from(rx))
+}
+
+impl TvgPlan {
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn to_file(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        let text = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        write_atomic(path, &text)
+    }
+
+    /// Writes the plan, then parses it back with TVGUtilContactPlan to check that A-SABR loads it.
+    pub fn write_checked(&self, path: &Path) -> Result<(), String> {
+        self.to_file(path)?;
+        let cp_file = path.to_string_lossy();
+        let (nodes, contacts) = TVGUtilContactPlan::parse::<NoManagement, EVLManager>(&cp_file)
+            .map_err(|e| format!("Failed to parse contact plan {}: {}", cp_file, e))?;
+        let (start, end) = self.time_range().unwrap_or_default();
+        println!(
+            "{}, {}: {} nodes, {} contacts, first contact at {}, last contact at {} ({:.1} h).",
+            time_now(),
+            cp_file,
+            nodes.len(),
+            contacts.len(),
+            start,
+            end,
+            (end - start) / 3600.0
+        );
+        Ok(())
+    }
+
+    /// Adds a contact under its (tx, rx) edge.
+    pub fn push(&mut self, contact: TvgContact) {
+        self.edges
+            .entry(edge_key(&contact.tx_node, &contact.rx_node))
+            .or_default()
+            .push(contact);
+    }
+
+    /// The plan in the A-SABR text format, nodes numbered in the order of the vertices like
+    /// TVGUtilContactPlan does. A-SABR contacts have one rate and delay, so a contact with several
+    /// characteristics becomes one contact per characteristic.
+    /// With a `marker` (for a Dispatcher, e.g. `evl`), it is written after `<end>`, and `mav` gives the
+    /// MAV of each priority level as ratios of the contact volume.
+    pub fn to_asabr(&self, marker: Option<&str>, mav: Option<[f64; 3]>) -> String {
+        let mut text = String::new();
+        for (id, name) in self.vertices.iter().enumerate() {
+            text.push_str(&format!("node {} {}\n", id, name));
+        }
+        text.push_str(&format!(
+            "\n# contact <from> <to> <start> <end>{} <rate> <delay>{}\n",
+            marker.map_or(String::new(), |m| format!(" {}", m)),
+            if mav.is_some() { " <mav_p0> <mav_p1> <mav_p2>" } else { "" }
+        ));
+        let id = |name: &str| self.vertices.iter().position(|v| v == name);
+        for c in self.contacts() {
+            let (Some(from), Some(to)) = (id(&c.tx_node), id(&c.rx_node)) else {
+                continue; // not a vertex, TVGUtilContactPlan would not load it either
+            };
+            for (i, ch) in c.characteristics.iter().enumerate() {
+                let start = ch.starting_at.max(c.start);
+                let end = c.characteristics.get(i + 1).map_or(c.end, |n| n.starting_at).min(c.end);
+                if start >= end {
+                    continue;
+                }
+                text.push_str(&format!("contact {} {} {} {}", from, to, start, end));
+                if let Some(marker) = marker {
+                    text.push_str(&format!(" {}", marker));
+                }
+                text.push_str(&format!(" {} {}", ch.bit_rate, ch.delay));
+                if let Some(mav) = mav {
+                    let volume = ch.bit_rate * (end - start);
+                    for ratio in mav {
+                        text.push_str(&format!(" {}", ratio * volume));
+                    }
+                }
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// Writes the plan in the A-SABR text format, then parses it back with ASABRContactPlan,
+    /// through a Dispatcher when there is a marker (`evl`, `evl` with MAVs for the priority EVL, or `qd`).
+    pub fn write_asabr_checked(&self, path: &Path, marker: Option<&str>, mav: Option<[f64; 3]>) -> Result<(), String> {
+        let mut dispatch: Dispatcher<ContactDispatcher> = Dispatcher::<ContactDispatcher>::new();
+        match (marker, mav) {
+            (None, None) => {}
+            (Some("evl"), None) => dispatch.add("evl", coerce_cm::<EVLManager>),
+            (Some("evl"), Some(_)) => dispatch.add("evl", coerce_cm::<PriorityEVLManager>),
+            (Some("qd"), None) => dispatch.add("qd", coerce_cm::<QDManager>),
+            (None, Some(_)) => return Err("MAVs need a manager marker".to_string()),
+            (Some(marker), _) => return Err(format!("Unsupported manager marker {}", marker)),
+        }
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        write_atomic(path, &self.to_asabr(marker, mav))?;
+        let cp_file = path.to_string_lossy();
+        let mut lexer = FileLexer::new(&cp_file).map_err(|e| format!("{}: {}", cp_file, e))?;
+        let mut cp = ASABRContactPlan::new();
+        let parsed = match marker {
+            None => cp
+                .parse::<NoManagement, EVLManager>(&mut lexer, None, None)
+                .map(|(nodes, contacts)| (nodes.len(), contacts.len())),
+            Some(_) => cp
+                .parse::<NoManagement, Box<dyn ContactManager>>(&mut lexer, None, Some(&dispatch))
+                .map(|(nodes, contacts)| (nodes.len(), contacts.len())),
+        };
+        let (nodes, contacts) = parsed.map_err(|e| format!("Failed to parse contact plan {}: {}", cp_file, e))?;
+        println!("{}, {}: {} nodes, {} contacts.", time_now(), cp_file, nodes, contacts);
+        Ok(())
+    }
+
+    pub fn contacts(&self) -> impl Iterator<Item = &TvgContact> {
+        self.edges.values().flatten()
+    }
+
+    pub fn contact_count(&self) -> usize {
+        self.edges.values().map(Vec::len).sum()
+    }
+
+    /// First contact start and last contact end.
+    pub fn time_range(&self) -> Option<(f64, f64)> {
+        self.contacts().fold(None, |range, c| match range {
+            None => Some((c.start, c.end)),
+            Some((s, e)) => Some((s.min(c.start), e.max(c.end))),
+        })
+    }
+
+    /// The window of `length` seconds starting `offset` seconds after the first contact,
+    /// so that the slice starts at its own earliest contact (clipped at the window start if needed).
+    /// The window is clipped at the last contact end: a 168 h plan rarely ends exactly 168 h after its
+    /// first contact, and its 168 h window is the whole plan.
+    pub fn window(&self, offset: f64, length: f64) -> Result<TvgPlan, String> {
+        let (first, last) = self.time_range().ok_or("No contact in the plan")?;
+        let (start, end) = (first + offset, (first + offset + length).min(last));
+        if start >= last {
+            return Err(format!(
+                "The plan ends before {:.1} h ({:.1} h)",
+                offset / 3600.0,
+                (last - first) / 3600.0
+            ));
+        }
+        let slice = self.slice(start, end);
+        if slice.contact_count() == 0 {
+            return Err(format!("No contact between {} and {}", start, end));
+        }
+        Ok(slice)
+    }
+
+    /// Only the given vertices and the contacts between them. The vertices keep their relative order,
+    /// so that A-SABR numbers them 0..keep.len() without gaps.
+    pub fn subset(&self, keep: &[String]) -> TvgPlan {
+        let vertices: Vec<String> = self.vertices.iter().filter(|v| keep.contains(v)).cloned().collect();
+        let edges = self
+            .edges
+            .iter()
+            .map(|(key, contacts)| {
+                let kept = contacts
+                    .iter()
+                    .filter(|c| vertices.contains(&c.tx_node) && vertices.contains(&c.rx_node))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (key.clone(), kept)
+            })
+            .filter(|(_, contacts)| !contacts.is_empty())
+            .collect();
+        TvgPlan {
+            vertices,
+            edges,
+            extra: self.extra.clone(),
+        }
+    }
+
+    /// Contacts in [start, end], clipped at the boundaries. All the vertices are kept.
+    pub fn slice(&self, start: f64, end: f64) -> TvgPlan {
+        let edges = self
+            .edges
+            .iter()
+            .map(|(key, contacts)| {
+                (key.clone(), contacts.iter().filter_map(|c| c.clipped(start, end)).collect::<Vec<_>>())
+            })
+            .filter(|(_, contacts)| !contacts.is_empty())
+            .collect();
+        TvgPlan {
+            vertices: self.vertices.clone(),
+            edges,
+            extra: self.extra.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A "168 h" plan whose last contact ends before first + 168 h.
+    fn short_plan() -> TvgPlan {
+        let mut plan = TvgPlan {
+            vertices: vec!["gs1".to_string(), "sat1".to_string()],
+            ..Default::default()
+        };
+        plan.push(TvgContact::new("gs1", "sat1", 1000.0, 1600.0, 9600.0, 0.01));
+        plan.push(TvgContact::new("sat1", "gs1", 167.0 * 3600.0, 167.5 * 3600.0, 9600.0, 0.01));
+        plan
+    }
+
+    #[test]
+    fn window_at_the_nominal_horizon() {
+        let plan = short_plan();
+        let window = plan.window(0.0, 168.0 * 3600.0).unwrap();
+        assert_eq!(window.contact_count(), plan.contact_count());
+        assert_eq!(window.time_range(), plan.time_range());
+    }
+
+    #[test]
+    fn window_after_the_plan() {
+        assert!(short_plan().window(168.0 * 3600.0, 3600.0).is_err());
+    }
+}
@@ -0,0 +1,314 @@
+//! Synthetic long-delay relay plans, where the one-way light time (OWLT) dominates: Earth ground stations,
+//! relay orbiters around the Moon or Mars and landers on its surface.
+//! * trunk contacts (Earth <-> relay): the daily pass of each ground station, cut by the occultation of the
+//!   relay behind the body once per orbit, with the OWLT of the body as delay,
+//! * proximity contacts (relay <-> lander): sparse passes, one per relay orbit with probability `relay_prob`,
+//!   with a light time of a few milliseconds.
+//!
+//! The OWLT of the Moon oscillates around 1.28 s over a month, the one of Mars drifts from a seeded value
+//! between 3 and 22 min. The plan is written as tvgutil JSON and in the A-SABR text format.
+use std::{f64::consts::PI, path::Path, str::FromStr};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    config::split_flags,
+    plan::{TvgContact, TvgPlan},
+    ringroad::LIGHT_SPEED_KM_S,
+    time_now,
+};
+
+pub const RELAY_USAGE: &str = "relay [options] <out> (writes <out>.json and <out>.cp)
+    --body <moon|mars>        remote body (mars)
+    --gs <usize>              Earth ground stations (3)
+    --relays <usize>          relay orbiters (2)
+    --landers <usize>         surface nodes (3)
+    --hours <f64>             horizon (72)
+    --seed <u64>              seed of the scenario (1)
+    --start <unix time>       start of the plan (1751839200)
+    --owlt <secs>             constant one-way light time, the body model by default
+    --gs-pass <hours>         daily pass of a ground station (8)
+    --relay-period <hours>    relay orbital period (moon 12, mars 2)
+    --occultation <ratio>     part of a relay orbit hidden from Earth (moon 0.2, mars 0.4)
+    --relay-pass <mins>       proximity pass duration (moon 30, mars 10)
+    --relay-prob <ratio>      probability of a proximity pass per relay orbit and lander (0.3)
+    --uplink-rate <bit/s>     Earth to relay (2000)
+    --downlink-rate <bit/s>   relay to Earth (200000)
+    --proximity-rate <bit/s>  relay <-> lander (100000)
+    --jitter <ratio>          pass duration jitter (0.2)";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Body {
+    Moon,
+    Mars,
+}
+
+impl FromStr for Body {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "moon" => Ok(Body::Moon),
+            "mars" => Ok(Body::Mars),
+            _ => Err(format!("Unknown body {}, expected moon or mars", s)),
+        }
+    }
+}
+
+impl Body {
+    fn name(&self) -> &'static str {
+        match self {
+            Body::Moon => "moon",
+            Body::Mars => "mars",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RelayConfig {
+    pub body: Body,
+    pub gs: usize,
+    pub relays: usize,
+    pub landers: usize,
+    pub hours: f64,
+    pub seed: u64,
+    pub start: f64,
+    /// None for the model of the body.
+    pub owlt_s: Option<f64>,
+    pub gs_pass_h: f64,
+    /// The ones below default to the body values when None.
+    pub relay_period_h: Option<f64>,
+    pub occultation: Option<f64>,
+    pub relay_pass_min: Option<f64>,
+    pub relay_prob: f64,
+    pub uplink_rate: f64,
+    pub downlink_rate: f64,
+    pub proximity_rate: f64,
+    pub jitter: f64,
+}
+
+impl Default for RelayConfig {
+    fn default() -> Self {
+        Self {
+            body: Body::Mars,
+            gs: 3,
+            relays: 2,
+            landers: 3,
+            hours: 72.0,
+            seed: 1,
+            start: 1751839200.0,
+            owlt_s: None,
+            gs_pass_h: 8.0,
+            relay_period_h: None,
+            occultation: None,
+            relay_pass_min: None,
+            relay_prob: 0.3,
+            uplink_rate: 2000.0,
+            downlink_rate: 200000.0,
+            proximity_rate: 100000.0,
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RelayConfig {
+    fn set(&mut self, flag: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("Invalid value for {}: {}", flag, value);
+        let f = || value.parse::<f64>().map_err(|_| invalid());
+        match flag {
+            "--body" => self.body = value.parse()?,
+            "--gs" => self.gs = value.parse().map_err(|_| invalid())?,
+            "--relays" => self.relays = value.parse().map_err(|_| invalid())?,
+            "--landers" => self.landers = value.parse().map_err(|_| invalid())?,
+            "--seed" => self.seed = value.parse().map_err(|_| invalid())?,
+            "--hours" => self.hours = f()?,
+            "--start" => self.start = f()?,
+            "--owlt" => self.owlt_s = Some(f()?),
+            "--gs-pass" => self.gs_pass_h = f()?,
+            "--relay-period" => self.relay_period_h = Some(f()?),
+            "--occultation" => self.occultation = Some(f()?),
+            "--relay-pass" => self.relay_pass_min = Some(f()?),
+            "--relay-prob" => self.relay_prob = f()?,
+            "--uplink-rate" => self.uplink_rate = f()?,
+            "--downlink-rate" => self.downlink_rate = f()?,
+            "--proximity-rate" => self.proximity_rate = f()?,
+            "--jitter" => self.jitter = f()?,
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+        Ok(())
+    }
+}
+
+/// OWLT in seconds at t seconds from the start of the plan.
+enum Owlt {
+    Constant(f64),
+    /// Moon: mean ± amplitude over the synodic month.
+    Monthly { phase: f64 },
+    /// Mars: linear drift in seconds per day, kept between conjunction and opposition.
+    Drift { at_start: f64, per_day: f64 },
+}
+
+impl Owlt {
+    fn at(&self, t: f64) -> f64 {
+        match self {
+            Owlt::Constant(owlt) => *owlt,
+            Owlt::Monthly { phase } => 1.28 + 0.06 * (2.0 * PI * t / (29.53 * 86400.0) + phase).sin(),
+            Owlt::Drift { at_start, per_day } => (at_start + per_day * t / 86400.0).clamp(182.0, 1342.0),
+        }
+    }
+}
+
+/// Intersection of two sorted lists of disjoint intervals.
+fn intersect(a: &[(f64, f64)], b: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let (mut i, mut j) = (0, 0);
+    let mut found = Vec::new();
+    while i < a.len() && j < b.len() {
+        let (start, end) = (a[i].0.max(b[j].0), a[i].1.min(b[j].1));
+        if start < end {
+            found.push((start, end));
+        }
+        if a[i].1 < b[j].1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    found
+}
+
+pub fn generate(config: &RelayConfig) -> TvgPlan {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let body = config.body;
+    let period = config.relay_period_h.unwrap_or(match body {
+        Body::Moon => 12.0,
+        Body::Mars => 2.0,
+    }) * 3600.0;
+    let occultation = config.occultation.unwrap_or(match body {
+        Body::Moon => 0.2,
+        Body::Mars => 0.4,
+    });
+    let relay_pass = config.relay_pass_min.unwrap_or(match body {
+        Body::Moon => 30.0,
+        Body::Mars => 10.0,
+    }) * 60.0;
+    let owlt = match (config.owlt_s, body) {
+        (Some(owlt), _) => Owlt::Constant(owlt),
+        (None, Body::Moon) => Owlt::Monthly {
+            phase: rng.random_range(0.0..2.0 * PI),
+        },
+        (None, Body::Mars) => Owlt::Drift {
+            at_start: rng.random_range(182.0..1342.0),
+            per_day: rng.random_range(-8.0..8.0),
+        },
+    };
+    let horizon = config.hours * 3600.0;
+    let jittered = |rng: &mut StdRng, duration: f64| duration * (1.0 + config.jitter * rng.random_range(-1.0..1.0));
+
+    let gs_names: Vec<String> = (1..=config.gs).map(|i| format!("earth{}", i)).collect();
+    let relay_names: Vec<String> = (1..=config.relays).map(|i| format!("{}_relay{}", body.name(), i)).collect();
+    let lander_names: Vec<String> = (1..=config.landers).map(|i| format!("{}_lander{}", body.name(), i)).collect();
+    let mut plan = TvgPlan {
+        vertices: gs_names.iter().chain(relay_names.iter()).chain(lander_names.iter()).cloned().collect(),
+        ..TvgPlan::default()
+    };
+
+    // daily passes of the ground stations, spread over the day
+    let gs_windows: Vec<Vec<(f64, f64)>> = (0..config.gs)
+        .map(|g| {
+            let offset = g as f64 * 86400.0 / config.gs as f64;
+            let mut windows = Vec::new();
+            let mut day = -86400.0;
+            while day < horizon {
+                let duration = jittered(&mut rng, config.gs_pass_h * 3600.0);
+                let (start, end) = ((day + offset).max(0.0), (day + offset + duration).min(horizon));
+                if start < end {
+                    windows.push((start, end));
+                }
+                day += 86400.0;
+            }
+            windows
+        })
+        .collect();
+    // orbit phases of the relays, visible from Earth out of the occultation
+    let phases: Vec<f64> = (0..config.relays).map(|_| rng.random_range(0.0..period)).collect();
+    for (r, phase) in phases.iter().enumerate() {
+        let mut visible = Vec::new();
+        let mut orbit = -phase;
+        while orbit < horizon {
+            let (start, end) = ((orbit + occultation * period).max(0.0), (orbit + period).min(horizon));
+            if start < end {
+                visible.push((start, end));
+            }
+            orbit += period;
+        }
+        for (g, windows) in gs_windows.iter().enumerate() {
+            for (start, end) in intersect(windows, &visible) {
+                let delay = owlt.at((start + end) / 2.0);
+                let (start, end) = (config.start + start, config.start + end);
+                plan.push(TvgContact::new(&gs_names[g], &relay_names[r], start, end, config.uplink_rate, delay));
+                plan.push(TvgContact::new(&relay_names[r], &gs_names[g], start, end, config.downlink_rate, delay));
+            }
+        }
+        // proximity passes, at most one per orbit and lander
+        let mut orbit = -phase;
+        while orbit < horizon {
+            for lander in lander_names.iter() {
+                if rng.random::<f64>() >= config.relay_prob {
+                    continue;
+                }
+                let half = jittered(&mut rng, relay_pass) / 2.0;
+                let middle = orbit + half + rng.random_range(0.0..(period - 2.0 * half).max(f64::EPSILON));
+                let (start, end) = ((middle - half).max(0.0), (middle + half).min(horizon));
+                let delay = rng.random_range(400.0..3000.0) / LIGHT_SPEED_KM_S;
+                if start >= end {
+                    continue;
+                }
+                let (start, end) = (config.start + start, config.start + end);
+                plan.push(TvgContact::new(&relay_names[r], lander, start, end, config.proximity_rate, delay));
+                plan.push(TvgContact::new(lander, &relay_names[r], start, end, config.proximity_rate, delay));
+            }
+            orbit += period;
+        }
+    }
+    // contacts of an edge in time order, as tvgutil writes them
+    for contacts in plan.edges.values_mut() {
+        contacts.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap());
+    }
+    plan
+}
+
+/// relay subcommand: `relay [options] <out>`
+pub fn run_relay(args: &[String]) -> Result<(), String> {
+    let (flags, positional) = split_flags(args)?;
+    let mut config = RelayConfig::default();
+    for (flag, value) in flags.iter() {
+        config.set(flag, value)?;
+    }
+    let [out] = positional.as_slice() else {
+        return Err("Expected <out>".to_string());
+    };
+    if config.gs == 0 || config.relays == 0 || config.hours <= 0.0 {
+        return Err("Expected at least one ground station and relay, and a positive horizon".to_string());
+    }
+    println!(
+        "{}, Generating a {} relay of {} ground stations, {} relays and {} landers over {} h (seed {}).",
+        time_now(),
+        config.body.name(),
+        config.gs,
+        config.relays,
+        config.landers,
+        config.hours,
+        config.seed
+    );
+    let plan = generate(&config);
+    if plan.contact_count() == 0 {
+        return Err("No contact generated, try a longer horizon or longer passes".to_string());
+    }
+    let delays: Vec<f64> = plan.contacts().flat_map(|c| c.characteristics.iter().map(|ch| ch.delay)).collect();
+    println!(
+        "Delays from {:.3} s to {:.3} s",
+        delays.iter().cloned().fold(f64::INFINITY, f64::min),
+        delays.iter().cloned().fold(0.0, f64::max)
+    );
+    plan.write_checked(&Path::new(out).with_extension("json"))?;
+    plan.write_asabr_checked(&Path::new(out).with_extension("cp"), None, None)
+}
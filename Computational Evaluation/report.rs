@@ -0,0 +1,292 @@
+//! Mean±std analysis of the structured results over elapse caps or bundle counts, replaces the
+//! `Mean+Std of Diffs Across Elapsed-Time Pairs_6 lines_*.py` scripts for any set of routers.
+//! * per router and axis value: mean±std of the mean compute times of the runs with failure ≤ max,
+//! * aligned: only the (plan, seed, contact manager, parameters) runs of a router that have failure ≤ max
+//!   at every axis value are kept, then the consecutive axis values are differenced run by run,
+//!   per router and over all routers.
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    path::Path,
+    str::FromStr,
+};
+
+use crate::{
+    checkpoint::write_atomic,
+    config::{split_flags, split_list},
+    results::{read_records, RunRecord},
+};
+
+pub const REPORT_USAGE: &str = "report <elapse|bundles> [options] <records.jsonl>...
+    --max-fail <percent>      failure rate filter, in % (1)
+    --routers <a,b,...>       routers to report, all by default
+    --out <file.csv>          also write the tables as CSV";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Axis {
+    /// Throttled runs over elapse_cap_s.
+    Elapse,
+    /// Runs without throttle over bundle_max_count.
+    Bundles,
+}
+
+impl FromStr for Axis {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "elapse" => Ok(Axis::Elapse),
+            "bundles" => Ok(Axis::Bundles),
+            _ => Err(format!("Unknown axis {}, expected elapse or bundles", s)),
+        }
+    }
+}
+
+impl Axis {
+    fn label(&self) -> &'static str {
+        match self {
+            Axis::Elapse => "Elapsed (s)",
+            Axis::Bundles => "Bundle count",
+        }
+    }
+
+    fn selects(&self, r: &RunRecord) -> bool {
+        match self {
+            Axis::Elapse => r.throttle_on,
+            Axis::Bundles => !r.throttle_on,
+        }
+    }
+
+    fn value(&self, r: &RunRecord) -> f64 {
+        match self {
+            Axis::Elapse => r.elapse_cap_s,
+            Axis::Bundles => r.bundle_max_count as f64,
+        }
+    }
+
+    /// What must be equal for two runs at different axis values to be compared.
+    fn run_key(&self, r: &RunRecord) -> String {
+        let plan = Path::new(&r.plan)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| r.plan.clone());
+        let other = match self {
+            Axis::Elapse => r.bundle_max_count.to_string(),
+            Axis::Bundles => String::new(), // the elapse cap is unused without throttle
+        };
+        format!(
+            "{}\t{}\t{}\t{}\t{},{},{}\t{}\t{:?}\t{}",
+            plan, r.tvgutil_seed, r.contact_manager, other, r.data_rate, r.bundle_size_min_ratio, r.bundle_size_max_ratio, r.traffic, r.priority_mix, r.lifetime
+        )
+    }
+}
+
+/// Mean and population std, NaN when empty as in the scripts.
+fn mean_std(vals: &[f64]) -> (f64, f64) {
+    if vals.is_empty() {
+        return (f64::NAN, f64::NAN);
+    }
+    let mean = vals.iter().sum::<f64>() / vals.len() as f64;
+    let var = vals.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / vals.len() as f64;
+    (mean, var.sqrt())
+}
+
+/// Mean compute time and failure rate of one run.
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    mean_ns: f64,
+    fail_rate: f64,
+}
+
+pub struct Report {
+    axis: Axis,
+    max_fail: f64,
+    xs: Vec<f64>,
+    routers: Vec<String>,
+    /// router -> axis value index -> run key -> sample, the last record of a run wins
+    samples: HashMap<String, Vec<BTreeMap<String, Sample>>>,
+}
+
+impl Report {
+    /// `max_fail` is a rate in [0, 1], `routers` empty for all of them in order of appearance.
+    pub fn new(records: &[RunRecord], axis: Axis, max_fail: f64, routers: &[String]) -> Self {
+        let selected: Vec<&RunRecord> = records
+            .iter()
+            .filter(|r| axis.selects(r))
+            .filter(|r| routers.is_empty() || routers.contains(&r.router))
+            .collect();
+        let mut xs: Vec<f64> = Vec::new();
+        for r in selected.iter() {
+            let x = axis.value(r);
+            if !xs.contains(&x) {
+                xs.push(x);
+            }
+        }
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let mut order: Vec<String> = Vec::new();
+        let mut samples: HashMap<String, Vec<BTreeMap<String, Sample>>> = HashMap::new();
+        for r in selected.iter() {
+            if !order.contains(&r.router) {
+                order.push(r.router.clone());
+            }
+            let i = xs.iter().position(|&x| x == axis.value(r)).unwrap();
+            let replaced = samples
+                .entry(r.router.clone())
+                .or_insert_with(|| vec![BTreeMap::new(); xs.len()])[i]
+                .insert(
+                    axis.run_key(r),
+                    Sample {
+                        mean_ns: r.metrics.mean_ns,
+                        fail_rate: r.metrics.fail_rate,
+                    },
+                );
+            if replaced.is_some() {
+                println!(
+                    "ERR: {}: {} ({}) on {} at {} {} is already recorded with the same parameters, the last record is kept",
+                    r.source, r.router, r.contact_manager, r.plan, axis.label(), xs[i]
+                );
+            }
+        }
+        // keep the order asked for
+        let routers = if routers.is_empty() {
+            order
+        } else {
+            routers.iter().filter(|r| order.contains(r)).cloned().collect()
+        };
+        Self {
+            axis,
+            max_fail,
+            xs,
+            routers,
+            samples,
+        }
+    }
+
+    /// Runs of a router with failure ≤ max at axis value i.
+    fn filtered(&self, router: &str, i: usize) -> Vec<f64> {
+        self.samples[router][i]
+            .values()
+            .filter(|s| s.fail_rate <= self.max_fail)
+            .map(|s| s.mean_ns)
+            .collect()
+    }
+
+    /// Runs of a router present with failure ≤ max at every axis value.
+    fn aligned_keys(&self, router: &str) -> Vec<String> {
+        let per_x = &self.samples[router];
+        per_x[0]
+            .keys()
+            .filter(|key| {
+                per_x.iter().all(|runs| runs.get(*key).is_some_and(|s| s.fail_rate <= self.max_fail))
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn aligned(&self, router: &str, keys: &[String], i: usize) -> Vec<f64> {
+        keys.iter().map(|k| self.samples[router][i][k].mean_ns).collect()
+    }
+
+    fn x(&self, i: usize) -> String {
+        format!("{}", self.xs[i])
+    }
+
+    /// Prints the four tables of the scripts, returns them as CSV rows
+    /// `table,x0,x1,router,count,mean_ns,std_ns`.
+    pub fn print(&self) -> String {
+        let mut csv = String::from("table,x0,x1,router,count,mean_ns,std_ns\n");
+        let mut row = |table: &str, x0: &str, x1: &str, router: &str, count: usize, (mean, std): (f64, f64)| {
+            writeln!(csv, "{},{},{},{},{},{:.2},{:.2}", table, x0, x1, router, count, mean, std).unwrap();
+            (mean, std)
+        };
+        let pct = self.max_fail * 100.0;
+        let label = self.axis.label();
+
+        println!("======Per-router mean±std filtered by (failure ≤{}%) at each {}:", pct, label);
+        for i in 0..self.xs.len() {
+            for router in self.routers.iter() {
+                let vals = self.filtered(router, i);
+                let (mean, std) = row("per_router", &self.x(i), "", router, vals.len(), mean_std(&vals));
+                println!("{} {:>7} | {:<30} | count={:>4} | mean={:10.2} ns | std={:10.2} ns", label, self.x(i), router, vals.len(), mean, std);
+            }
+        }
+
+        let aligned: Vec<Vec<String>> = self.routers.iter().map(|r| self.aligned_keys(r)).collect();
+        println!("======Per-router mean±std of the differences, aligned (failure ≤{}%) at every {}:", pct, label);
+        for i in 0..self.xs.len().saturating_sub(1) {
+            for (router, keys) in self.routers.iter().zip(aligned.iter()) {
+                let v0 = self.aligned(router, keys, i);
+                let v1 = self.aligned(router, keys, i + 1);
+                let diffs: Vec<f64> = v0.iter().zip(v1.iter()).map(|(a, b)| b - a).collect();
+                let (mean, std) = row("per_router_diff", &self.x(i), &self.x(i + 1), router, diffs.len(), mean_std(&diffs));
+                println!("Diff {:>7} - {:<7} | {:<30} | count={:>4} | mean={:10.2} ns | std={:10.2} ns", self.x(i + 1), self.x(i), router, diffs.len(), mean, std);
+            }
+        }
+
+        println!("======Overall mean±std, aligned (failure ≤{}%):", pct);
+        let overall = |i: usize| -> Vec<f64> {
+            self.routers
+                .iter()
+                .zip(aligned.iter())
+                .flat_map(|(router, keys)| self.aligned(router, keys, i))
+                .collect()
+        };
+        for i in 0..self.xs.len() {
+            let vals = overall(i);
+            let (mean, std) = row("overall", &self.x(i), "", "", vals.len(), mean_std(&vals));
+            println!("{} {:>7} | count={:>5} | mean={:10.2} ns | std={:10.2} ns", label, self.x(i), vals.len(), mean, std);
+        }
+
+        println!("======Overall mean±std of the differences, aligned (failure ≤{}%):", pct);
+        for i in 0..self.xs.len().saturating_sub(1) {
+            let diffs: Vec<f64> = overall(i).iter().zip(overall(i + 1).iter()).map(|(a, b)| b - a).collect();
+            let (mean, std) = row("overall_diff", &self.x(i), &self.x(i + 1), "", diffs.len(), mean_std(&diffs));
+            println!("Diff {:>7} - {:<7} | count={:>5} | mean={:10.2} ns | std={:10.2} ns", self.x(i + 1), self.x(i), diffs.len(), mean, std);
+        }
+        csv
+    }
+}
+
+/// report subcommand: `report <elapse|bundles> [options] <records.jsonl>...`
+pub fn run_report(args: &[String]) -> Result<(), String> {
+    let (flags, positional) = split_flags(args)?;
+    let mut max_fail = 1.0;
+    let mut routers: Vec<String> = Vec::new();
+    let mut out: Option<String> = None;
+    for (flag, value) in flags.iter() {
+        match flag.as_str() {
+            "--max-fail" => {
+                max_fail = value.parse().map_err(|_| format!("Invalid value for --max-fail: {}", value))?
+            }
+            "--routers" => routers = split_list(value),
+            "--out" => out = Some(value.clone()),
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+    let (axis, files) = positional
+        .split_first()
+        .ok_or_else(|| "Missing axis".to_string())?;
+    let axis: Axis = axis.parse()?;
+    if files.is_empty() {
+        return Err("No records file".to_string());
+    }
+    let mut records = Vec::new();
+    for file in files.iter() {
+        records.extend(read_records(Path::new(file))?);
+    }
+    let report = Report::new(&records, axis, max_fail / 100.0, &routers);
+    if report.xs.len() < 2 {
+        return Err(format!("Less than two {} values in the records", axis.label()));
+    }
+    println!(
+        "Discovered {} values: [{}], routers: [{}]",
+        axis.label(),
+        report.xs.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(", "),
+        report.routers.join(", ")
+    );
+    let csv = report.print();
+    if let Some(out) = out {
+        write_atomic(Path::new(&out), &csv)?;
+        println!("Tables written to {}", out);
+    }
+    Ok(())
+}
@@ -0,0 +1,184 @@
+//! Structured results: one JSON object per (plan, contact manager, router, parameter set) run,
+//! appended to `<results_dir>/runs.jsonl`. The analysis reads these records instead of the console output.
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    percentile,
+    traffic::{LifetimeConfig, TrafficConfig},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpsnRecord {
+    pub check_size: bool,
+    pub check_priority: bool,
+    pub max_entries: usize,
+}
+
+/// Compute time statistics of one router, in nanoseconds.
+/// The inner percentiles are missing from the records imported from legacy logs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Metrics {
+    pub mean_ns: f64,
+    pub std_ns: f64,
+    /// Over the bundles routed + 1, as in the legacy logs.
+    pub fail_rate: f64,
+    /// Part of fail_rate where a path of the plan reaches the destination, but none before the bundle expires,
+    /// whatever the capacity, the rest are capacity or no path failures. Only with a lifetime model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expired_rate: Option<f64>,
+    /// Bundles per second.
+    pub sch_rate: f64,
+    pub sum_s: f64,
+    pub p0: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p5: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p10: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p20: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p50: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p80: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p90: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p95: Option<f64>,
+    pub p100: f64,
+}
+
+/// `value / over`, 0 when `over` is 0 (no bundle routed, or routed in no time):
+/// serde_json writes inf and NaN as null, which read_records can not read back.
+pub fn ratio(value: f64, over: f64) -> f64 {
+    if over > 0.0 {
+        value / over
+    } else {
+        0.0
+    }
+}
+
+impl Metrics {
+    pub fn from_times(times: &[Duration], sch_rate: f64, fail_rate: f64) -> Self {
+        let sum_ns = times.iter().sum::<Duration>().as_nanos() as f64; // nanoseconds
+        let mut ns_vals: Vec<f64> = times.iter().map(|d| d.as_nanos() as f64).collect();
+        ns_vals.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let mean_ns = ratio(sum_ns, ns_vals.len() as f64);
+        let var_ns = ratio(ns_vals.iter().map(|&v| (v - mean_ns).powi(2)).sum::<f64>(), ns_vals.len() as f64);
+        Self {
+            mean_ns,
+            std_ns: var_ns.sqrt(),
+            fail_rate,
+            expired_rate: None,
+            sch_rate,
+            sum_s: sum_ns / 1e9,
+            p0: percentile(&ns_vals, 0.0),
+            p5: Some(percentile(&ns_vals, 5.0)),
+            p10: Some(percentile(&ns_vals, 10.0)),
+            p20: Some(percentile(&ns_vals, 20.0)),
+            p50: Some(percentile(&ns_vals, 50.0)),
+            p80: Some(percentile(&ns_vals, 80.0)),
+            p90: Some(percentile(&ns_vals, 90.0)),
+            p95: Some(percentile(&ns_vals, 95.0)),
+            p100: percentile(&ns_vals, 100.0),
+        }
+    }
+}
+
+/// Metrics of the bundles of one priority, in a priority-mixed run.
+/// Its rates are over the `bundle_count` bundles of the priority, not + 1 like the run metrics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriorityMetrics {
+    pub priority: u8,
+    pub bundle_count: usize,
+    pub metrics: Metrics,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// "run" for the harness, "legacy:<log file>" for the imported logs.
+    #[serde(default = "default_source")]
+    pub source: String,
+    /// Contact plan path as given to the harness.
+    pub plan: String,
+    pub nodes: u16,
+    pub contacts: usize,
+    /// First contact start and last contact end.
+    pub start_time: f64,
+    pub end_time: f64,
+    pub horizon_s: f64,
+    pub contact_manager: String,
+    pub router: String,
+    /// None for the routers that do not take SpsnOptions.
+    pub spsn_options: Option<SpsnRecord>,
+    /// Rate that sized the bundles: the fixed one of the config, or the average of the plan over the contact time.
+    pub data_rate: f64,
+    pub avg_contact_volume: f64,
+    pub bundle_max_count: usize,
+    /// Bundles routed before the elapse cap, unknown for throttled legacy runs.
+    pub bundle_count: Option<usize>,
+    pub bundle_size_min_ratio: f64,
+    pub bundle_size_max_ratio: f64,
+    pub bundle_min_size: f64,
+    pub bundle_max_size: f64,
+    pub elapse_cap_s: f64,
+    pub throttle_on: bool,
+    pub tvgutil_seed: u64,
+    /// Bundle i (from 0) is drawn from StdRng::seed_from_u64(bundle_seed_first + i).
+    pub bundle_seed_first: u64,
+    /// Bundle creation times, `start` for the records written before the traffic models.
+    #[serde(default)]
+    pub traffic: TrafficConfig,
+    /// Bundle lifetimes, `none` (the last contact end) for the records written before the lifetime models.
+    #[serde(default)]
+    pub lifetime: LifetimeConfig,
+    /// Weights of the priorities 0, 1 and 2, empty when every bundle has priority 1.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub priority_mix: Vec<f64>,
+    pub metrics: Metrics,
+    /// Per priority, with a priority mix.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub priority_metrics: Vec<PriorityMetrics>,
+}
+
+fn default_source() -> String {
+    "run".to_string()
+}
+
+pub fn records_path(results_dir: &str) -> PathBuf {
+    Path::new(results_dir).join("runs.jsonl")
+}
+
+/// Appends one record as a single line, a partial last line (crash) is skipped by read_records.
+pub fn append_record(path: &Path, record: &RunRecord) -> Result<(), String> {
+    let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+    let mut f = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    writeln!(f, "{}", line)
+        .and_then(|_| f.sync_data())
+        .map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+pub fn read_records(path: &Path) -> Result<Vec<RunRecord>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut records = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<RunRecord>(line) {
+            Ok(record) => records.push(record),
+            Err(e) => eprintln!("ERR: {}:{}: skipped record: {}", path.display(), i + 1, e),
+        }
+    }
+    Ok(records)
+}
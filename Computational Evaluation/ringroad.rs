@@ -0,0 +1,270 @@
+//! Synthetic ring road contact plans, without tvgutil nor TLE files: seeded ground stations and LEO
+//! satellites on circular orbits, sampled every `step` seconds.
+//! * ground station passes above the minimum elevation, uplink (gs -> sat) and downlink (sat -> gs),
+//!   each pass duration is jittered around its middle by up to ±jitter,
+//! * inter-satellite links while two satellites are within the ISL range,
+//! * the delay of a contact is the light time at the middle of the contact.
+//!
+//! The output is the tvgutil JSON read by TVGUtilContactPlan, the same seed gives the same plan.
+use std::{f64::consts::PI, path::Path};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    config::split_flags,
+    plan::{TvgContact, TvgPlan},
+    time_now,
+};
+
+const EARTH_RADIUS_KM: f64 = 6371.0;
+const EARTH_MU_KM3_S2: f64 = 398600.4418;
+const EARTH_ROTATION_RAD_S: f64 = 7.2921159e-5;
+pub const LIGHT_SPEED_KM_S: f64 = 299792.458;
+
+pub const RINGROAD_USAGE: &str = "ringroad [options] <out.json>
+    --gs <usize>              ground stations (10)
+    --sats <usize>            satellites (10)
+    --hours <f64>             horizon (24)
+    --seed <u64>              seed of the scenario (1)
+    --start <unix time>       time of the first sample (1751839200)
+    --altitude <km>           satellites altitude (550)
+    --min-elevation <deg>     minimum elevation of a pass (10)
+    --isl-range <km>          maximum inter-satellite link range, 0 for none (1000)
+    --uplink-rate <bit/s>     ground station to satellite (9600)
+    --downlink-rate <bit/s>   satellite to ground station (9600)
+    --isl-rate <bit/s>        satellite to satellite (9600)
+    --jitter <ratio>          pass duration jitter (0.2)
+    --step <secs>             sampling step (10)";
+
+#[derive(Debug, Clone)]
+pub struct RingRoadConfig {
+    pub gs: usize,
+    pub sats: usize,
+    pub hours: f64,
+    pub seed: u64,
+    pub start: f64,
+    pub altitude_km: f64,
+    pub min_elevation_deg: f64,
+    pub isl_range_km: f64,
+    pub uplink_rate: f64,
+    pub downlink_rate: f64,
+    pub isl_rate: f64,
+    pub jitter: f64,
+    pub step_s: f64,
+}
+
+impl Default for RingRoadConfig {
+    fn default() -> Self {
+        Self {
+            gs: 10,
+            sats: 10,
+            hours: 24.0,
+            seed: 1,
+            start: 1751839200.0,
+            altitude_km: 550.0,
+            min_elevation_deg: 10.0,
+            isl_range_km: 1000.0,
+            uplink_rate: 9600.0,
+            downlink_rate: 9600.0,
+            isl_rate: 9600.0,
+            jitter: 0.2,
+            step_s: 10.0,
+        }
+    }
+}
+
+impl RingRoadConfig {
+    fn set(&mut self, flag: &str, value: &str) -> Result<(), String> {
+        let invalid = || format!("Invalid value for {}: {}", flag, value);
+        let f = || value.parse::<f64>().map_err(|_| invalid());
+        match flag {
+            "--gs" => self.gs = value.parse().map_err(|_| invalid())?,
+            "--sats" => self.sats = value.parse().map_err(|_| invalid())?,
+            "--seed" => self.seed = value.parse().map_err(|_| invalid())?,
+            "--hours" => self.hours = f()?,
+            "--start" => self.start = f()?,
+            "--altitude" => self.altitude_km = f()?,
+            "--min-elevation" => self.min_elevation_deg = f()?,
+            "--isl-range" => self.isl_range_km = f()?,
+            "--uplink-rate" => self.uplink_rate = f()?,
+            "--downlink-rate" => self.downlink_rate = f()?,
+            "--isl-rate" => self.isl_rate = f()?,
+            "--jitter" => self.jitter = f()?,
+            "--step" => self.step_s = f()?,
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+        Ok(())
+    }
+}
+
+type Vec3 = [f64; 3];
+
+fn distance(a: &Vec3, b: &Vec3) -> f64 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+/// Circular orbit, Earth centered inertial frame.
+struct Orbit {
+    radius: f64,
+    inclination: f64,
+    raan: f64,
+    phase: f64,
+    period: f64,
+}
+
+impl Orbit {
+    fn position(&self, t: f64) -> Vec3 {
+        let u = self.phase + 2.0 * PI * t / self.period;
+        let (su, cu) = u.sin_cos();
+        let (so, co) = self.raan.sin_cos();
+        let (si, ci) = self.inclination.sin_cos();
+        [
+            self.radius * (co * cu - so * su * ci),
+            self.radius * (so * cu + co * su * ci),
+            self.radius * su * si,
+        ]
+    }
+}
+
+/// Ground station on the rotating Earth.
+struct Station {
+    lat: f64,
+    lon: f64,
+}
+
+impl Station {
+    fn position(&self, t: f64) -> Vec3 {
+        let lon = self.lon + EARTH_ROTATION_RAD_S * t;
+        [
+            EARTH_RADIUS_KM * self.lat.cos() * lon.cos(),
+            EARTH_RADIUS_KM * self.lat.cos() * lon.sin(),
+            EARTH_RADIUS_KM * self.lat.sin(),
+        ]
+    }
+
+    fn elevation(&self, gs: &Vec3, sat: &Vec3) -> f64 {
+        let d = [sat[0] - gs[0], sat[1] - gs[1], sat[2] - gs[2]];
+        let up = (d[0] * gs[0] + d[1] * gs[1] + d[2] * gs[2]) / (distance(sat, gs) * EARTH_RADIUS_KM);
+        up.clamp(-1.0, 1.0).asin()
+    }
+}
+
+/// A window of consecutive samples where `visible` holds, as sample indices [first, last].
+fn windows(samples: usize, mut visible: impl FnMut(usize) -> bool) -> Vec<(usize, usize)> {
+    let mut found = Vec::new();
+    let mut open: Option<usize> = None;
+    for k in 0..samples {
+        match (visible(k), open) {
+            (true, None) => open = Some(k),
+            (false, Some(first)) => {
+                found.push((first, k - 1));
+                open = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(first) = open {
+        found.push((first, samples - 1));
+    }
+    found
+}
+
+pub fn generate(config: &RingRoadConfig) -> TvgPlan {
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let radius = EARTH_RADIUS_KM + config.altitude_km;
+    let period = 2.0 * PI * (radius.powi(3) / EARTH_MU_KM3_S2).sqrt();
+    let stations: Vec<Station> = (0..config.gs)
+        .map(|_| Station {
+            // uniform on the sphere between ±60° of latitude
+            lat: rng.random_range(-(60f64.to_radians().sin())..60f64.to_radians().sin()).asin(),
+            lon: rng.random_range(-PI..PI),
+        })
+        .collect();
+    let orbits: Vec<Orbit> = (0..config.sats)
+        .map(|_| Orbit {
+            radius,
+            inclination: rng.random_range(45f64..98.0).to_radians(),
+            raan: rng.random_range(0.0..2.0 * PI),
+            phase: rng.random_range(0.0..2.0 * PI),
+            period,
+        })
+        .collect();
+
+    let horizon = config.hours * 3600.0;
+    let samples = (horizon / config.step_s).floor() as usize + 1;
+    let time = |k: usize| k as f64 * config.step_s;
+    let gs_pos: Vec<Vec<Vec3>> = stations.iter().map(|s| (0..samples).map(|k| s.position(time(k))).collect()).collect();
+    let sat_pos: Vec<Vec<Vec3>> = orbits.iter().map(|o| (0..samples).map(|k| o.position(time(k))).collect()).collect();
+    let gs_names: Vec<String> = (1..=config.gs).map(|i| format!("gs{}", i)).collect();
+    let sat_names: Vec<String> = (1..=config.sats).map(|i| format!("sat{}", i)).collect();
+
+    let mut plan = TvgPlan {
+        vertices: gs_names.iter().chain(sat_names.iter()).cloned().collect(),
+        ..TvgPlan::default()
+    };
+    let min_elevation = config.min_elevation_deg.to_radians();
+    for (g, station) in stations.iter().enumerate() {
+        for s in 0..orbits.len() {
+            let passes = windows(samples, |k| station.elevation(&gs_pos[g][k], &sat_pos[s][k]) >= min_elevation);
+            for (first, last) in passes {
+                // jitter around the middle of the pass, kept in the horizon
+                let (start, end) = (time(first), time(last) + config.step_s);
+                let middle = (start + end) / 2.0;
+                let half = (end - start) / 2.0 * (1.0 + config.jitter * rng.random_range(-1.0..1.0));
+                let (start, end) = ((middle - half).max(0.0), (middle + half).min(horizon));
+                if end - start < config.step_s {
+                    continue;
+                }
+                let k = ((middle / config.step_s).round() as usize).min(samples - 1);
+                let delay = distance(&gs_pos[g][k], &sat_pos[s][k]) / LIGHT_SPEED_KM_S;
+                let (start, end) = (config.start + start, config.start + end);
+                plan.push(TvgContact::new(&gs_names[g], &sat_names[s], start, end, config.uplink_rate, delay));
+                plan.push(TvgContact::new(&sat_names[s], &gs_names[g], start, end, config.downlink_rate, delay));
+            }
+        }
+    }
+    if config.isl_range_km > 0.0 {
+        for a in 0..orbits.len() {
+            for b in a + 1..orbits.len() {
+                let links = windows(samples, |k| distance(&sat_pos[a][k], &sat_pos[b][k]) <= config.isl_range_km);
+                for (first, last) in links {
+                    let (start, end) = (time(first), (time(last) + config.step_s).min(horizon));
+                    let k = (first + last) / 2;
+                    let delay = distance(&sat_pos[a][k], &sat_pos[b][k]) / LIGHT_SPEED_KM_S;
+                    let (start, end) = (config.start + start, config.start + end);
+                    plan.push(TvgContact::new(&sat_names[a], &sat_names[b], start, end, config.isl_rate, delay));
+                    plan.push(TvgContact::new(&sat_names[b], &sat_names[a], start, end, config.isl_rate, delay));
+                }
+            }
+        }
+    }
+    plan
+}
+
+/// ringroad subcommand: `ringroad [options] <out.json>`
+pub fn run_ringroad(args: &[String]) -> Result<(), String> {
+    let (flags, positional) = split_flags(args)?;
+    let mut config = RingRoadConfig::default();
+    for (flag, value) in flags.iter() {
+        config.set(flag, value)?;
+    }
+    let [out] = positional.as_slice() else {
+        return Err("Expected <out.json>".to_string());
+    };
+    if config.gs + config.sats < 2 || config.hours <= 0.0 || config.step_s <= 0.0 {
+        return Err("Expected at least 2 nodes, a positive horizon and step".to_string());
+    }
+    println!(
+        "{}, Generating a ring road of {} ground stations and {} satellites over {} h (seed {}).",
+        time_now(),
+        config.gs,
+        config.sats,
+        config.hours,
+        config.seed
+    );
+    let plan = generate(&config);
+    if plan.contact_count() == 0 {
+        return Err("No contact generated, try more nodes, a longer horizon or a lower minimum elevation".to_string());
+    }
+    plan.write_checked(Path::new(out))
+}
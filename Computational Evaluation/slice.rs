@@ -0,0 +1,104 @@
+//! Contact plan slicing: time windows of one long tvgutil plan, so that every horizon comes from the same
+//! scenario instead of one tvgutil run per duration (grid-search/generate_cp.sh).
+//! * `--hours 24:168:4` (or `24,48,72`): the first N hours of the plan, written to `<stem>_{N}h.json`,
+//! * `--windows 24 --step 12`: windows of 24 h every 12 h until the end of the plan, `<stem>_w{k}_24h.json`.
+//!
+//! Contacts crossing a window boundary are clipped, so each slice starts at its own earliest contact.
+//! Every slice is parsed back with TVGUtilContactPlan to check that A-SABR loads it.
+use std::path::Path;
+
+use crate::{config::split_flags, plan::TvgPlan, time_now};
+
+pub const SLICE_USAGE: &str = "slice <plan.json> <out_dir> (--hours <spec> | --windows <hours> [--step <hours>])
+    --hours <a:b:step|a,b,..>  horizons from the first contact, in hours
+    --windows <hours>          consecutive windows of this length
+    --step <hours>             start of a window to the next (window length)";
+
+/// `24:168:4` like `seq 24 4 168`, or `24,48,72`.
+pub fn parse_values(spec: &str) -> Option<Vec<f64>> {
+    let parts: Vec<&str> = spec.split(':').collect();
+    if let [start, end, step] = parts.as_slice() {
+        let (start, end, step): (f64, f64, f64) = (start.parse().ok()?, end.parse().ok()?, step.parse().ok()?);
+        if step <= 0.0 {
+            return None;
+        }
+        let count = ((end - start) / step).floor() as usize + 1;
+        return Some((0..count).map(|i| start + i as f64 * step).collect());
+    }
+    spec.split(',').map(|h| h.trim().parse().ok()).collect()
+}
+
+/// slice subcommand: `slice <plan.json> <out_dir> (--hours <spec> | --windows <hours> [--step <hours>])`
+pub fn run_slice(args: &[String]) -> Result<(), String> {
+    let (flags, positional) = split_flags(args)?;
+    let mut hours: Option<Vec<f64>> = None;
+    let mut window: Option<f64> = None;
+    let mut step: Option<f64> = None;
+    for (flag, value) in flags.iter() {
+        let invalid = || format!("Invalid value for {}: {}", flag, value);
+        match flag.as_str() {
+            "--hours" => hours = Some(parse_values(value).ok_or_else(invalid)?),
+            "--windows" => window = Some(value.parse().map_err(|_| invalid())?),
+            "--step" => step = Some(value.parse().map_err(|_| invalid())?),
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+    let [plan_file, out_dir] = positional.as_slice() else {
+        return Err("Expected <plan.json> <out_dir>".to_string());
+    };
+    if hours.is_some() == window.is_some() {
+        return Err("Expected one of --hours or --windows".to_string());
+    }
+    let plan = TvgPlan::from_file(Path::new(plan_file))?;
+    let (first, last) = plan
+        .time_range()
+        .ok_or_else(|| format!("No contact in {}", plan_file))?;
+    let stem = Path::new(plan_file)
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    println!(
+        "{}, Slicing {}: {} nodes, {} contacts over {:.1} h.",
+        time_now(),
+        plan_file,
+        plan.vertices.len(),
+        plan.contact_count(),
+        (last - first) / 3600.0
+    );
+
+    // (offset, length, file name) of every slice, in seconds
+    let mut slices: Vec<(f64, f64, String)> = Vec::new();
+    match (hours, window) {
+        (Some(hours), None) => {
+            for h in hours {
+                slices.push((0.0, h * 3600.0, format!("{}_{}h.json", stem, h)));
+            }
+        }
+        (None, Some(w)) => {
+            let step = step.unwrap_or(w);
+            if w <= 0.0 || step <= 0.0 {
+                return Err("The window and step must be positive".to_string());
+            }
+            let mut k = 0;
+            while (k as f64 * step + w) * 3600.0 <= last - first {
+                slices.push((k as f64 * step * 3600.0, w * 3600.0, format!("{}_w{}_{}h.json", stem, k, w)));
+                k += 1;
+            }
+        }
+        _ => unreachable!(),
+    }
+    if slices.is_empty() {
+        return Err(format!("{} is too short for the windows asked", plan_file));
+    }
+    let mut failed = 0;
+    for (offset, length, name) in slices.iter() {
+        let path = Path::new(out_dir).join(name);
+        if let Err(e) = plan.window(*offset, *length).and_then(|slice| slice.write_checked(&path)) {
+            println!("ERR: {}: {}", path.display(), e);
+            failed += 1;
+        }
+    }
+    println!("{}, {} slices written to {}, {} failed.", time_now(), slices.len() - failed, out_dir, failed);
+    Ok(())
+}
@@ -0,0 +1,360 @@
+//! Contact plan statistics, from the rates and delays of the plan file (A-SABR keeps the rate of a contact
+//! private to its manager, so run_plan can only count durations):
+//! * distributions of the contact durations, volumes and rates,
+//! * per node: contacts, neighbours and volume, sent and received,
+//! * ground links (one end is a ground station, by name prefix) versus inter-satellite links,
+//! * temporal connectivity: at regular times, the fraction of (source, destination) pairs with a
+//!   time-respecting path until the end of the plan, with the delays but regardless of capacity.
+//!
+//! The statistics are printed and written as JSON, next to the plan or to the results of run_plan.
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, BinaryHeap},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    checkpoint::write_atomic,
+    config::{split_flags, split_list},
+    convert::read_plan,
+    percentile,
+    plan::TvgPlan,
+    time_now,
+};
+
+pub const STATS_USAGE: &str = "stats [options] <plan>...
+    --ground <a,b,..>         name prefixes of the ground stations (gs,earth)
+    --samples <usize>         start times of the temporal connectivity (24)
+    --out <dir>               where to write <plan>.stats.json, next to each plan by default";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Distribution {
+    pub count: usize,
+    pub mean: f64,
+    pub min: f64,
+    pub p10: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub max: f64,
+}
+
+impl Distribution {
+    fn new(mut vals: Vec<f64>) -> Self {
+        if vals.is_empty() {
+            return Self::default();
+        }
+        vals.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        Self {
+            count: vals.len(),
+            mean: vals.iter().sum::<f64>() / vals.len() as f64,
+            min: vals[0],
+            p10: percentile(&vals, 10.0),
+            p50: percentile(&vals, 50.0),
+            p90: percentile(&vals, 90.0),
+            max: vals[vals.len() - 1],
+        }
+    }
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "mean= {:.2}, min= {:.2}, p10= {:.2}, p50= {:.2}, p90= {:.2}, max= {:.2}",
+            self.mean, self.min, self.p10, self.p50, self.p90, self.max
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NodeStats {
+    pub name: String,
+    pub ground: bool,
+    pub contacts_out: usize,
+    pub contacts_in: usize,
+    /// Distinct neighbours, sent to and received from.
+    pub degree_out: usize,
+    pub degree_in: usize,
+    pub volume_out: f64,
+    pub volume_in: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LinkSplit {
+    pub contacts: usize,
+    pub volume: f64,
+    pub duration_s: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlanStats {
+    pub plan: String,
+    pub nodes: usize,
+    pub contacts: usize,
+    pub start_time: f64,
+    pub end_time: f64,
+    pub duration_s: Distribution,
+    pub volume: Distribution,
+    pub rate: Distribution,
+    pub delay_s: Distribution,
+    /// Contacts per rate, the rates as written in the plan.
+    pub rates: BTreeMap<String, usize>,
+    pub total_volume: f64,
+    pub ground_links: LinkSplit,
+    pub isl: LinkSplit,
+    pub per_node: Vec<NodeStats>,
+    /// (time, fraction of the pairs reachable from then until the end of the plan)
+    pub reachability: Vec<(f64, f64)>,
+    pub mean_reachability: f64,
+}
+
+/// The (to, start, end, delay) segments from every node, one per characteristic of the contacts,
+/// nodes by vertex index as A-SABR numbers them.
+pub fn contact_segments(plan: &TvgPlan) -> Vec<Vec<(usize, f64, f64, f64)>> {
+    let index = |v: &str| plan.vertices.iter().position(|n| n == v);
+    let mut out: Vec<Vec<(usize, f64, f64, f64)>> = vec![Vec::new(); plan.vertices.len()];
+    for c in plan.contacts() {
+        let (Some(from), Some(to)) = (index(&c.tx_node), index(&c.rx_node)) else {
+            continue;
+        };
+        for (i, ch) in c.characteristics.iter().enumerate() {
+            let start = ch.starting_at.max(c.start);
+            let end = c.characteristics.get(i + 1).map_or(c.end, |n| n.starting_at).min(c.end);
+            if start < end {
+                out[from].push((to, start, end, ch.delay));
+            }
+        }
+    }
+    out
+}
+
+/// Earliest arrival at every node from `source` at `time`, over the contact segments, whatever their capacity.
+/// The search stops at `until`, the nodes not reached by then are at infinity.
+pub fn earliest_arrivals(out: &[Vec<(usize, f64, f64, f64)>], source: usize, time: f64, until: f64) -> Vec<f64> {
+    #[derive(PartialEq)]
+    struct Entry(f64, usize);
+    impl Eq for Entry {}
+    impl PartialOrd for Entry {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for Entry {
+        fn cmp(&self, other: &Self) -> Ordering {
+            other.0.partial_cmp(&self.0).unwrap_or(Ordering::Equal) // min-heap
+        }
+    }
+    let mut arrival = vec![f64::INFINITY; out.len()];
+    arrival[source] = time;
+    let mut heap = BinaryHeap::from([Entry(time, source)]);
+    while let Some(Entry(at, node)) = heap.pop() {
+        if at > arrival[node] {
+            continue;
+        }
+        for &(to, start, end, delay) in out[node].iter() {
+            if end <= at {
+                continue;
+            }
+            let reached = at.max(start) + delay;
+            if reached < arrival[to] && reached <= until {
+                arrival[to] = reached;
+                heap.push(Entry(reached, to));
+            }
+        }
+    }
+    arrival
+}
+
+pub fn plan_stats(name: &str, plan: &TvgPlan, ground: &[String], samples: usize) -> PlanStats {
+    let index = |v: &str| plan.vertices.iter().position(|n| n == v);
+    let is_ground = |v: &str| ground.iter().any(|p| v.starts_with(p.as_str()));
+    let mut per_node: Vec<NodeStats> = plan
+        .vertices
+        .iter()
+        .map(|v| NodeStats {
+            name: v.clone(),
+            ground: is_ground(v),
+            ..NodeStats::default()
+        })
+        .collect();
+    let mut neighbours_out: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); plan.vertices.len()];
+    let mut neighbours_in: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); plan.vertices.len()];
+    let out = contact_segments(plan);
+    let (mut durations, mut volumes, mut rates, mut delays) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    let mut rate_counts: BTreeMap<String, usize> = BTreeMap::new();
+    let (mut ground_links, mut isl) = (LinkSplit::default(), LinkSplit::default());
+    for c in plan.contacts() {
+        let (Some(from), Some(to)) = (index(&c.tx_node), index(&c.rx_node)) else {
+            continue; // not a vertex, A-SABR does not load it
+        };
+        let volume = c.volume();
+        durations.push(c.end - c.start);
+        volumes.push(volume);
+        let split = if is_ground(&c.tx_node) || is_ground(&c.rx_node) {
+            &mut ground_links
+        } else {
+            &mut isl
+        };
+        split.contacts += 1;
+        split.volume += volume;
+        split.duration_s += c.end - c.start;
+        per_node[from].contacts_out += 1;
+        per_node[from].volume_out += volume;
+        per_node[to].contacts_in += 1;
+        per_node[to].volume_in += volume;
+        neighbours_out[from].insert(to);
+        neighbours_in[to].insert(from);
+        for ch in c.characteristics.iter() {
+            rates.push(ch.bit_rate);
+            delays.push(ch.delay);
+            *rate_counts.entry(ch.bit_rate.to_string()).or_insert(0) += 1;
+        }
+    }
+    for (n, node) in per_node.iter_mut().enumerate() {
+        node.degree_out = neighbours_out[n].len();
+        node.degree_in = neighbours_in[n].len();
+    }
+    let (start_time, end_time) = plan.time_range().unwrap_or_default();
+
+    let n = plan.vertices.len();
+    let mut reachability = Vec::new();
+    if n > 1 {
+        for k in 0..samples {
+            let time = start_time + (end_time - start_time) * k as f64 / samples as f64;
+            let reached: usize = (0..n)
+                .map(|s| {
+                    let arrival = earliest_arrivals(&out, s, time, end_time);
+                    (0..n).filter(|&d| d != s && arrival[d] <= end_time).count()
+                })
+                .sum();
+            reachability.push((time, reached as f64 / (n * (n - 1)) as f64));
+        }
+    }
+    let mean_reachability = if reachability.is_empty() {
+        0.0
+    } else {
+        reachability.iter().map(|(_, r)| r).sum::<f64>() / reachability.len() as f64
+    };
+    PlanStats {
+        plan: name.to_string(),
+        nodes: n,
+        contacts: durations.len(),
+        start_time,
+        end_time,
+        total_volume: volumes.iter().sum(),
+        duration_s: Distribution::new(durations),
+        volume: Distribution::new(volumes),
+        rate: Distribution::new(rates),
+        delay_s: Distribution::new(delays),
+        rates: rate_counts,
+        ground_links,
+        isl,
+        per_node,
+        reachability,
+        mean_reachability,
+    }
+}
+
+impl PlanStats {
+    pub fn print(&self) {
+        println!(
+            "{}, {}: {} nodes, {} contacts over {:.1} h, total volume {:.3e}",
+            time_now(),
+            self.plan,
+            self.nodes,
+            self.contacts,
+            (self.end_time - self.start_time) / 3600.0,
+            self.total_volume
+        );
+        println!("    duration (s): {}", self.duration_s);
+        println!("    volume:       {}", self.volume);
+        println!("    rate:         {}", self.rate);
+        println!("    delay (s):    {}", self.delay_s);
+        println!(
+            "    rates: {}",
+            self.rates.iter().map(|(r, c)| format!("{} ({} contacts)", r, c)).collect::<Vec<_>>().join(", ")
+        );
+        for (label, split) in [("ground links", &self.ground_links), ("ISL", &self.isl)] {
+            println!(
+                "    {:12}: {:>6} contacts, volume {:.3e} ({:.1}%), {:.1} h",
+                label,
+                split.contacts,
+                split.volume,
+                split.volume / self.total_volume.max(f64::MIN_POSITIVE) * 100.0,
+                split.duration_s / 3600.0
+            );
+        }
+        println!("    {:<16} {:>6} {:>6} {:>6} {:>6} {:>12} {:>12}", "node", "c_out", "c_in", "d_out", "d_in", "vol_out", "vol_in");
+        for node in self.per_node.iter() {
+            println!(
+                "    {:<16} {:>6} {:>6} {:>6} {:>6} {:>12.3e} {:>12.3e}{}",
+                node.name,
+                node.contacts_out,
+                node.contacts_in,
+                node.degree_out,
+                node.degree_in,
+                node.volume_out,
+                node.volume_in,
+                if node.ground { " (ground)" } else { "" }
+            );
+        }
+        println!(
+            "    reachable pairs: mean {:.1}%, {}",
+            self.mean_reachability * 100.0,
+            self.reachability
+                .iter()
+                .map(|(t, r)| format!("+{:.1}h {:.1}%", (t - self.start_time) / 3600.0, r * 100.0))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        write_atomic(path, &text)
+    }
+}
+
+/// `<dir>/<plan stem>.stats.json`, dir defaults to the one of the plan.
+pub fn stats_path(plan: &Path, dir: Option<&Path>) -> PathBuf {
+    let stem = plan.file_stem().unwrap_or_default().to_string_lossy();
+    let dir = dir.or_else(|| plan.parent()).unwrap_or(Path::new(""));
+    dir.join(format!("{}.stats.json", stem))
+}
+
+pub const DEFAULT_GROUND: [&str; 2] = ["gs", "earth"];
+pub const DEFAULT_SAMPLES: usize = 24;
+
+/// stats subcommand: `stats [options] <plan>...`
+pub fn run_stats(args: &[String]) -> Result<(), String> {
+    let (flags, positional) = split_flags(args)?;
+    let mut ground: Vec<String> = DEFAULT_GROUND.iter().map(|p| p.to_string()).collect();
+    let mut samples = DEFAULT_SAMPLES;
+    let mut out: Option<String> = None;
+    for (flag, value) in flags.iter() {
+        match flag.as_str() {
+            "--ground" => ground = split_list(value),
+            "--samples" => samples = value.parse().map_err(|_| format!("Invalid value for --samples: {}", value))?,
+            "--out" => out = Some(value.clone()),
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+    if positional.is_empty() {
+        return Err("No plan".to_string());
+    }
+    if let Some(dir) = out.as_ref() {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir, e))?;
+    }
+    for file in positional.iter() {
+        let (plan, _) = read_plan(Path::new(file))?;
+        let stats = plan_stats(file, &plan, &ground, samples);
+        stats.print();
+        let path = stats_path(Path::new(file), out.as_deref().map(Path::new));
+        stats.write(&path)?;
+        println!("{}, Statistics written to {}", time_now(), path.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,132 @@
+//! Node subsets of one tvgutil plan, so that the node-count axis comes from one scenario instead of one
+//! tvgutil scenario per node count (`create_rr_scenario --gs N --sats N`).
+//! * `--nodes gs1,sat3,...`: the given vertices, by name or by index in the plan,
+//! * `--counts 18:30:2 [--seed 1]`: nested random subsets, the first N vertices of one seeded shuffle,
+//!   so that every subset contains the smaller ones.
+//!
+//! Only the contacts between kept nodes remain, and the nodes are renumbered 0..N in the order of the plan.
+//! The mapping is written next to each plan as `<plan>.nodes.csv` (`new_id,old_id,name`).
+use std::path::Path;
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::{checkpoint::write_atomic, config::split_flags, plan::TvgPlan, slice::parse_values, time_now};
+
+pub const SUBSET_USAGE: &str = "subset <plan.json> <out_dir> (--nodes <a,b,..> | --counts <spec> [--seed <u64>])
+    --nodes <a,b,..>          vertices to keep, names or indices in the plan
+    --counts <a:b:step|a,b,..> node counts of the nested random subsets
+    --seed <u64>              seed of the shuffle (1)";
+
+fn write_subset(plan: &TvgPlan, keep: &[String], path: &Path) -> Result<(), String> {
+    let subset = plan.subset(keep);
+    if subset.contact_count() == 0 {
+        return Err("No contact between the kept nodes".to_string());
+    }
+    let mut mapping = String::from("new_id,old_id,name\n");
+    for (new_id, name) in subset.vertices.iter().enumerate() {
+        let old_id = plan.vertices.iter().position(|v| v == name).unwrap();
+        mapping.push_str(&format!("{},{},{}\n", new_id, old_id, name));
+    }
+    let isolated: Vec<&String> = subset
+        .vertices
+        .iter()
+        .filter(|v| !subset.contacts().any(|c| &c.tx_node == *v || &c.rx_node == *v))
+        .collect();
+    if !isolated.is_empty() {
+        println!(
+            "ERR: {}: {} nodes without contact: {}",
+            path.display(),
+            isolated.len(),
+            isolated.iter().map(|v| v.as_str()).collect::<Vec<_>>().join(", ")
+        );
+    }
+    subset.write_checked(path)?;
+    let mut mapping_path = path.as_os_str().to_owned();
+    mapping_path.push(".nodes.csv");
+    write_atomic(Path::new(&mapping_path), &mapping)
+}
+
+/// Node counts of `--counts`, None if one of them is not a whole number ≥ 0.
+fn parse_counts(spec: &str) -> Option<Vec<usize>> {
+    parse_values(spec)?
+        .into_iter()
+        .map(|c| (c >= 0.0 && c.fract() == 0.0).then_some(c as usize))
+        .collect()
+}
+
+/// subset subcommand: `subset <plan.json> <out_dir> (--nodes <a,b,..> | --counts <spec> [--seed <u64>])`
+pub fn run_subset(args: &[String]) -> Result<(), String> {
+    let (flags, positional) = split_flags(args)?;
+    let mut nodes: Option<Vec<String>> = None;
+    let mut counts: Option<Vec<usize>> = None;
+    let mut seed: u64 = 1;
+    for (flag, value) in flags.iter() {
+        let invalid = || format!("Invalid value for {}: {}", flag, value);
+        match flag.as_str() {
+            "--nodes" => nodes = Some(value.split(',').map(|s| s.trim().to_string()).collect()),
+            "--counts" => counts = Some(parse_counts(value).ok_or_else(invalid)?),
+            "--seed" => seed = value.parse().map_err(|_| invalid())?,
+            _ => return Err(format!("Unknown option {}", flag)),
+        }
+    }
+    let [plan_file, out_dir] = positional.as_slice() else {
+        return Err("Expected <plan.json> <out_dir>".to_string());
+    };
+    if nodes.is_some() == counts.is_some() {
+        return Err("Expected one of --nodes or --counts".to_string());
+    }
+    let plan = TvgPlan::from_file(Path::new(plan_file))?;
+    let stem = Path::new(plan_file)
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    println!(
+        "{}, Sampling {}: {} nodes, {} contacts.",
+        time_now(),
+        plan_file,
+        plan.vertices.len(),
+        plan.contact_count()
+    );
+
+    // (vertices, file name) of every subset
+    let mut subsets: Vec<(Vec<String>, String)> = Vec::new();
+    if let Some(nodes) = nodes {
+        let mut keep = Vec::new();
+        for node in nodes.iter() {
+            let name = if plan.vertices.contains(node) {
+                node.clone()
+            } else {
+                node.parse::<usize>()
+                    .ok()
+                    .and_then(|i| plan.vertices.get(i).cloned())
+                    .ok_or_else(|| format!("Unknown node {} in {}", node, plan_file))?
+            };
+            if !keep.contains(&name) {
+                keep.push(name);
+            }
+        }
+        let name = format!("{}_n{}.json", stem, keep.len());
+        subsets.push((keep, name));
+    }
+    if let Some(counts) = counts {
+        let mut order = plan.vertices.clone();
+        order.shuffle(&mut StdRng::seed_from_u64(seed));
+        for &count in counts.iter() {
+            if count < 2 || count > order.len() {
+                return Err(format!("Can not keep {} of the {} nodes", count, order.len()));
+            }
+            subsets.push((order[..count].to_vec(), format!("{}_n{}_s{}.json", stem, count, seed)));
+        }
+    }
+    let mut failed = 0;
+    for (keep, name) in subsets.iter() {
+        let path = Path::new(out_dir).join(name);
+        if let Err(e) = write_subset(&plan, keep, &path) {
+            println!("ERR: {}: {}", path.display(), e);
+            failed += 1;
+        }
+    }
+    println!("{}, {} subsets written to {}, {} failed.", time_now(), subsets.len() - failed, out_dir, failed);
+    Ok(())
+}
@@ -0,0 +1,191 @@
+//! Bundle creation times over the horizon of the plan, each bundle is routed at its creation time.
+//! * `start`: every bundle at the first contact start (the earlier evaluations),
+//! * `poisson`: a Poisson process conditioned on the bundle count, i.e. sorted uniform times,
+//! * `periodic`: evenly spaced, the first one at the first contact start,
+//! * `bursty`: bursts of `burst_size` bundles, the bursts are Poisson and the bundles of a burst
+//!   are created within `burst_spread_s` of its start.
+//!
+//! The times are sorted, so a throttled run stops early in the plan: lower `--bundles` to cover the horizon.
+//!
+//! The bundle lifetimes are drawn from [LifetimeConfig], relative to the creation times.
+use std::{fmt, str::FromStr};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrafficModel {
+    Start,
+    Poisson,
+    Periodic,
+    Bursty,
+}
+
+impl FromStr for TrafficModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "start" => Ok(Self::Start),
+            "poisson" => Ok(Self::Poisson),
+            "periodic" => Ok(Self::Periodic),
+            "bursty" => Ok(Self::Bursty),
+            _ => Err(format!("Unknown traffic model {}, expected start, poisson, periodic or bursty", s)),
+        }
+    }
+}
+
+impl fmt::Display for TrafficModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Start => "start",
+            Self::Poisson => "poisson",
+            Self::Periodic => "periodic",
+            Self::Bursty => "bursty",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TrafficConfig {
+    pub model: TrafficModel,
+    /// Seed of the creation times, the bundles keep their own seeds.
+    pub seed: u64,
+    pub burst_size: usize,
+    pub burst_spread_s: f64,
+}
+
+impl Default for TrafficConfig {
+    fn default() -> Self {
+        Self {
+            model: TrafficModel::Start,
+            seed: 1,
+            burst_size: 100,
+            burst_spread_s: 60.0,
+        }
+    }
+}
+
+impl TrafficConfig {
+    /// Creation time of each of the `count` bundles, sorted, in [start_time, end_time).
+    pub fn creation_times(&self, count: usize, start_time: f64, end_time: f64) -> Vec<f64> {
+        let horizon = end_time - start_time;
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut times: Vec<f64> = match self.model {
+            TrafficModel::Start => vec![start_time; count],
+            TrafficModel::Poisson => (0..count).map(|_| start_time + rng.random_range(0.0..horizon)).collect(),
+            TrafficModel::Periodic => (0..count).map(|i| start_time + horizon * i as f64 / count as f64).collect(),
+            TrafficModel::Bursty => {
+                let burst_size = self.burst_size.max(1);
+                let mut times = Vec::with_capacity(count);
+                while times.len() < count {
+                    let burst = start_time + rng.random_range(0.0..horizon);
+                    let spread = self.burst_spread_s.min(end_time - burst);
+                    for _ in 0..burst_size.min(count - times.len()) {
+                        times.push(if spread > 0.0 { burst + rng.random_range(0.0..spread) } else { burst });
+                    }
+                }
+                times
+            }
+        };
+        times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        times
+    }
+}
+
+impl fmt::Display for TrafficConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.model {
+            TrafficModel::Start => write!(f, "start"),
+            TrafficModel::Bursty => write!(f, "bursty (seed {}, {} bundles within {} s)", self.seed, self.burst_size, self.burst_spread_s),
+            model => write!(f, "{} (seed {})", model, self.seed),
+        }
+    }
+}
+
+/// Bundle lifetimes, from the creation time of each bundle.
+/// * `none`: every bundle expires at the last contact end (the earlier evaluations),
+/// * `fixed`: `lifetime_s`,
+/// * `uniform`: in [min_lifetime_s, lifetime_s],
+/// * `exponential`: of mean `lifetime_s`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LifetimeModel {
+    None,
+    Fixed,
+    Uniform,
+    Exponential,
+}
+
+impl FromStr for LifetimeModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "fixed" => Ok(Self::Fixed),
+            "uniform" => Ok(Self::Uniform),
+            "exponential" => Ok(Self::Exponential),
+            _ => Err(format!("Unknown lifetime model {}, expected none, fixed, uniform or exponential", s)),
+        }
+    }
+}
+
+impl fmt::Display for LifetimeModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::None => "none",
+            Self::Fixed => "fixed",
+            Self::Uniform => "uniform",
+            Self::Exponential => "exponential",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LifetimeConfig {
+    pub model: LifetimeModel,
+    pub lifetime_s: f64,
+    pub min_lifetime_s: f64,
+}
+
+impl Default for LifetimeConfig {
+    fn default() -> Self {
+        Self {
+            model: LifetimeModel::None,
+            lifetime_s: 3600.0,
+            min_lifetime_s: 0.0,
+        }
+    }
+}
+
+impl LifetimeConfig {
+    /// Expiration of a bundle created at `creation_time`, drawn from the rng of the bundle.
+    pub fn expiration(&self, creation_time: f64, end_time: f64, rng: &mut StdRng) -> f64 {
+        let lifetime = match self.model {
+            LifetimeModel::None => return end_time,
+            LifetimeModel::Fixed => self.lifetime_s,
+            LifetimeModel::Uniform if self.min_lifetime_s < self.lifetime_s => rng.random_range(self.min_lifetime_s..=self.lifetime_s),
+            LifetimeModel::Uniform => self.lifetime_s,
+            // inverse transform, 1 - u in (0, 1]
+            LifetimeModel::Exponential => -self.lifetime_s * (1.0 - rng.random::<f64>()).ln(),
+        };
+        creation_time + lifetime
+    }
+}
+
+impl fmt::Display for LifetimeConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.model {
+            LifetimeModel::None => write!(f, "none"),
+            LifetimeModel::Fixed => write!(f, "fixed {} s", self.lifetime_s),
+            LifetimeModel::Uniform => write!(f, "uniform [{}, {}] s", self.min_lifetime_s, self.lifetime_s),
+            LifetimeModel::Exponential => write!(f, "exponential of mean {} s", self.lifetime_s),
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! `EvlContact` for A-SABR contacts managed by the priority `EVLManager` (myevl.rs),
+//! so that `compute_route_evl` runs on the routes returned by `Router::route`.
+//! Needs the a_sabr crate with myevl.rs integrated (see myevl.md), so main.rs does not declare this module.
+use a_sabr::{
+    contact::{Contact, ContactInfo},
+    contact_manager::myevl::EVLManager,
+    node_manager::NodeManager,
+    types::{Date, NodeID, Priority},
+};
+
+use crate::my_r_evl::EvlContact;
+
+/// A contact seen at the priority level of the bundle, C.MAV(p) is the volume.
+pub struct AsabrContact<'a> {
+    pub info: &'a ContactInfo,
+    pub manager: &'a EVLManager,
+    pub priority: Priority,
+}
+
+impl EvlContact for AsabrContact<'_> {
+    fn start(&self) -> f64 {
+        self.info.start
+    }
+    fn end(&self) -> f64 {
+        self.info.end
+    }
+    fn rate(&self) -> f64 {
+        self.manager.rate
+    }
+    fn volume(&self) -> f64 {
+        // same as EVLManager::get_mav (private), 0 for an undefined priority
+        self.manager
+            .mav
+            .get(self.priority as usize)
+            .copied()
+            .unwrap_or(0.0)
+    }
+}
+
+/// Finds the contact of a plan matching a hop of a route (same nodes and same time window).
+pub fn lookup<'a, NM: NodeManager>(
+    contacts: &'a [Contact<NM, EVLManager>],
+    from: NodeID,
+    to: NodeID,
+    start: Date,
+    end: Date,
+    priority: Priority,
+) -> Option<AsabrContact<'a>> {
+    contacts
+        .iter()
+        .find(|c| c.info.from == from && c.info.to == to && c.info.start == start && c.info.end == end)
+        .map(|c| AsabrContact {
+            info: &c.info,
+            manager: &c.manager,
+            priority,
+        })
+}
@@ -0,0 +1,182 @@
+//! Loads standalone `Contact`s from contact plan files.
+//! * A-SABR text format (e.g. priority_test.cp): `contact <from> <to> <start> <end> ...` with the
+//!   manager marker (`evl`, `qd`, `eto`, `seg`...) either before or after `<rate> <delay>`,
+//!   followed by the optional MAVs of each priority level. `node` lines and `#` comments are skipped.
+//! * ION commands: `a contact <start> <end> <from> <to> <rate>` and `a range <start> <end> <from> <to> <owlt>`.
+//!   Times are relative (`+60`), plain seconds, or absolute UTC (`2025/07/10-00:00:00`).
+//!   ION rates are in bytes/s and are converted to the bits/s of the A-SABR format.
+//!
+//! Contact ids are given in file order starting at 1, like the hand written contacts in main.rs.
+//! C.MAV is taken at the requested priority level when the plan provides MAVs,
+//! otherwise it is the contact capacity rate * (end - start).
+use std::fs;
+
+use crate::my_r_evl::Contact;
+
+/// Loads a contact plan, ION if it contains `a contact` commands, A-SABR text format otherwise.
+pub fn load_contacts(path: &str, priority: usize) -> Result<Vec<Contact>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let is_ion = text.lines().any(|l| {
+        let mut words = l.split_whitespace();
+        words.next() == Some("a") && words.next() == Some("contact")
+    });
+    if is_ion {
+        parse_ion(&text, path)
+    } else {
+        parse_asabr(&text, path, priority)
+    }
+}
+
+pub fn parse_asabr(text: &str, path: &str, priority: usize) -> Result<Vec<Contact>, String> {
+    let mut contacts = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.first() {
+            Some(&"contact") => {}
+            _ => continue, // empty lines, comments, nodes
+        }
+        let at = |msg: &str| format!("{}:{}: {}", path, i + 1, msg);
+        if tokens.len() < 7 {
+            return Err(at("expected `contact <from> <to> <start> <end> <rate> <delay>`"));
+        }
+        // the manager marker is the first non numeric token after <end>
+        let params: Vec<&str> = tokens[5..]
+            .iter()
+            .copied()
+            .filter(|t| t.parse::<f64>().is_err())
+            .collect();
+        if params.len() > 1 {
+            return Err(at("more than one manager marker"));
+        }
+        let values: Vec<f64> = tokens[5..]
+            .iter()
+            .filter_map(|t| t.parse::<f64>().ok())
+            .collect();
+        if values.len() < 2 {
+            return Err(at("missing <rate> or <delay>"));
+        }
+        let start = parse_num(tokens[3]).map_err(|e| at(&e))?;
+        let end = parse_num(tokens[4]).map_err(|e| at(&e))?;
+        let data_rate = values[0];
+        let mavs = &values[2..];
+        let max_volume = if mavs.is_empty() {
+            data_rate * (end - start)
+        } else {
+            mavs.get(priority).copied().unwrap_or(0.0)
+        };
+        contacts.push(Contact {
+            id: contacts.len() as u32 + 1,
+            from: parse_node(tokens[1]).map_err(|e| at(&e))?,
+            to: parse_node(tokens[2]).map_err(|e| at(&e))?,
+            start,
+            end,
+            data_rate,
+            max_volume,
+            delay: values[1],
+        });
+    }
+    Ok(contacts)
+}
+
+// parse_ion, parse_ion_time and days_from_civil are copies of from_ion, parse_ion_time and days_from_civil
+// of convert.rs in the evaluation (`a_sabr convert`), the canonical ones, kept in sync by hand since the
+// two binaries share no crate. Only the epoch of the absolute times differs.
+pub fn parse_ion(text: &str, path: &str) -> Result<Vec<Contact>, String> {
+    let mut contacts = Vec::new();
+    // (start, end, from, to, owlt), applied once all the contacts are known
+    let mut ranges: Vec<(f64, f64, u32, u32, f64)> = Vec::new();
+    let mut epoch: Option<f64> = None;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.len() < 2 || tokens[0] != "a" {
+            continue;
+        }
+        let at = |msg: &str| format!("{}:{}: {}", path, i + 1, msg);
+        if tokens[1] != "contact" && tokens[1] != "range" {
+            continue;
+        }
+        if tokens.len() < 7 {
+            return Err(at(&format!("expected `a {} <start> <end> <from> <to> <value>`", tokens[1])));
+        }
+        let start = parse_ion_time(tokens[2], &mut epoch).map_err(|e| at(&e))?;
+        let end = parse_ion_time(tokens[3], &mut epoch).map_err(|e| at(&e))?;
+        let from = parse_node(tokens[4]).map_err(|e| at(&e))?;
+        let to = parse_node(tokens[5]).map_err(|e| at(&e))?;
+        let value = parse_num(tokens[6]).map_err(|e| at(&e))?;
+        if tokens[1] == "contact" {
+            let value = value * 8.0; // bytes/s to bits/s
+            contacts.push(Contact {
+                id: contacts.len() as u32 + 1,
+                from,
+                to,
+                start,
+                end,
+                data_rate: value,
+                max_volume: value * (end - start),
+                delay: 0.0,
+            });
+        } else {
+            ranges.push((start, end, from, to, value));
+        }
+    }
+    // ION ranges are symmetric unless both directions are declared
+    for contact in contacts.iter_mut() {
+        for &(start, end, from, to, owlt) in ranges.iter() {
+            let same_pair = (from == contact.from && to == contact.to)
+                || (from == contact.to && to == contact.from);
+            if same_pair && start <= contact.start && contact.start < end {
+                contact.delay = owlt;
+                if from == contact.from {
+                    break; // exact direction wins
+                }
+            }
+        }
+    }
+    Ok(contacts)
+}
+
+fn parse_num(token: &str) -> Result<f64, String> {
+    token
+        .parse::<f64>()
+        .map_err(|_| format!("invalid number `{}`", token))
+}
+
+fn parse_node(token: &str) -> Result<u32, String> {
+    token
+        .parse::<u32>()
+        .map_err(|_| format!("invalid node id `{}`", token))
+}
+
+/// ION time: `+<seconds>` relative to the plan start, plain seconds, or `yyyy/mm/dd-hh:mm:ss` UTC.
+/// Absolute times are made relative to the first absolute time seen in the file.
+fn parse_ion_time(token: &str, epoch: &mut Option<f64>) -> Result<f64, String> {
+    if let Some(rel) = token.strip_prefix('+') {
+        return parse_num(rel);
+    }
+    if !token.contains('/') {
+        return parse_num(token);
+    }
+    let err = || format!("invalid ION time `{}`", token);
+    let (date, time) = token.split_once('-').ok_or_else(err)?;
+    let d: Vec<i64> = date.split('/').map(|v| v.parse().map_err(|_| err())).collect::<Result<_, _>>()?;
+    let t: Vec<f64> = time.split(':').map(|v| v.parse().map_err(|_| err())).collect::<Result<_, _>>()?;
+    if d.len() != 3 || t.len() != 3 {
+        return Err(err());
+    }
+    let secs = days_from_civil(d[0], d[1], d[2]) as f64 * 86_400.0 + t[0] * 3600.0 + t[1] * 60.0 + t[2];
+    let origin = *epoch.get_or_insert(secs);
+    Ok(secs - origin)
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date.
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
@@ -0,0 +1,278 @@
+//! Differential check between A-SABR routing with the priority EVLManager (myevl.rs) and the standalone R.EVL.
+//! Usage: evl_diff <cp_file> [max_hops]
+//!
+//! Bundles are routed with SpsnMpt and CgrMpt the same way the priority main.rs does. The checker keeps its own
+//! copy of the contacts, updated with `schedule_tx` along every returned route, and flags:
+//! * a returned route whose R.EVL (compute_route_evl, at the bundle priority) is below the bundle size,
+//! * a rejected bundle whose best candidate route (tutorial selection order) had R.EVL >= bundle size.
+//!
+//! myevl.rs checks C.EVL hop by hop in dry_run_tx instead of R.EVL on the whole route,
+//! any flagged bundle is a case where both checks disagree.
+mod asabr_contact;
+mod my_r_evl;
+mod route_selection;
+
+use std::{cell::RefCell, env, rc::Rc};
+
+use a_sabr::{
+    bundle::Bundle,
+    contact::Contact,
+    contact_manager::{myevl::EVLManager, ContactManager},
+    contact_plan::{asabr_file_lexer::FileLexer, from_asabr_lexer::ASABRContactPlan},
+    node_manager::none::NoManagement,
+    parsing::{Dispatcher, Lexer, Parser, ParsingState},
+    route_storage::cache::TreeCache,
+    routing::{
+        aliases::{CgrMpt, SpsnMpt},
+        Router,
+    },
+    types::{Date, NodeID},
+};
+
+use asabr_contact::{lookup, AsabrContact};
+use my_r_evl::compute_route_evl;
+use route_selection::{RouteMetrics, SelectionPolicy};
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() < 2 {
+        eprintln!("Usage: {} <cp_file> [max_hops]", args[0]);
+        std::process::exit(1);
+    }
+    let max_hops = match args.get(2) {
+        Some(v) => v.parse::<usize>().unwrap_or_else(|_| {
+            eprintln!("ERR: Invalid max_hops value: {}", v);
+            std::process::exit(1);
+        }),
+        None => 5,
+    };
+    println!("Working with cp: {}", args[1]);
+
+    let (nodes, contacts) = parse_plan(&args[1]);
+    println!("\nContact plan loaded with {} nodes and {} contacts", nodes.len(), contacts.len());
+
+    let bundles = vec![
+        Bundle { source: 0, destinations: vec![2], priority: 2, size: 2.0, expiration: 10000.0 },
+        Bundle { source: 0, destinations: vec![2], priority: 1, size: 4.0, expiration: 10000.0 },
+        Bundle { source: 0, destinations: vec![2], priority: 0, size: 8.0, expiration: 10000.0 },
+        Bundle { source: 0, destinations: vec![3], priority: 1, size: 5.0, expiration: 10000.0 },
+        Bundle { source: 0, destinations: vec![4], priority: 2, size: 5.0, expiration: 10000.0 },
+        Bundle { source: 0, destinations: vec![2], priority: 2, size: 1.0, expiration: 10000.0 },
+    ];
+
+    let mut flagged = 0;
+
+    println!("\n=== CHECKING SPSN ROUTING ===");
+    let table = Rc::new(RefCell::new(TreeCache::new(true, false, 10)));
+    let mut spsn = SpsnMpt::<NoManagement, EVLManager>::new(nodes.clone(), contacts.clone(), table, false);
+    flagged += check_router(&mut spsn, contacts.clone(), &bundles, max_hops);
+
+    println!("\n=== CHECKING CGR ROUTING ===");
+    let table = Rc::new(RefCell::new(TreeCache::new(true, false, 10)));
+    let mut cgr = CgrMpt::<NoManagement, EVLManager>::new(nodes.clone(), contacts.clone(), table, false);
+    flagged += check_router(&mut cgr, contacts.clone(), &bundles, max_hops);
+
+    println!("\n{} discrepancies between A-SABR and the standalone R.EVL.", flagged);
+    if flagged > 0 {
+        std::process::exit(2);
+    }
+}
+
+fn parse_plan(
+    cp_file: &str,
+) -> (
+    Vec<a_sabr::node::Node<NoManagement>>,
+    Vec<Contact<NoManagement, EVLManager>>,
+) {
+    let mut mylexer = FileLexer::new(cp_file).unwrap_or_else(|e| {
+        eprintln!("ERR: Can not open {}: {}", cp_file, e);
+        std::process::exit(1);
+    });
+    let mut cp = ASABRContactPlan::new();
+    // concrete EVLManager for the "evl" markers, to read rate and MAVs of the contacts
+    let mut contact_dispatch: Dispatcher<fn(&mut dyn Lexer) -> ParsingState<EVLManager>> =
+        Dispatcher::new();
+    contact_dispatch.add("evl", <EVLManager as Parser<EVLManager>>::parse);
+    cp.parse::<NoManagement, EVLManager>(&mut mylexer, None, Some(&contact_dispatch))
+        .unwrap_or_else(|e| {
+            eprintln!("ERR: Failed to parse {}: {}", cp_file, e);
+            std::process::exit(1);
+        })
+}
+
+/// Routes the bundles in sequence and compares each decision with the standalone R.EVL, returns the flag count.
+fn check_router<R>(
+    router: &mut R,
+    mut mirror: Vec<Contact<NoManagement, EVLManager>>,
+    bundles: &[Bundle],
+    max_hops: usize,
+) -> usize
+where
+    R: Router<NoManagement, EVLManager>,
+{
+    let at_time: Date = 0.0;
+    let mut flagged = 0;
+    for (i, bundle) in bundles.iter().enumerate() {
+        println!(
+            "\nBundle {} (priority: {}, size: {}, {} -> {})",
+            i + 1, bundle.priority, bundle.size, bundle.source, bundle.destinations[0]
+        );
+        match router.route(bundle.source, bundle, at_time, &Vec::new()) {
+            Some((_, route)) => {
+                let hops: Option<Vec<AsabrContact>> = route
+                    .iter()
+                    .map(|hop| lookup(&mirror, hop.from, hop.to, hop.start, hop.end, bundle.priority))
+                    .collect();
+                let Some(hops) = hops else {
+                    println!("  FLAG: route uses a contact unknown to the plan");
+                    flagged += 1;
+                    continue;
+                };
+                let refs: Vec<&AsabrContact> = hops.iter().collect();
+                let route_evl = compute_route_evl(&refs);
+                println!("  Route found, {} hops, R.EVL = {}", refs.len(), route_evl);
+                if route_evl < bundle.size {
+                    println!("  FLAG: R.EVL {} < bundle size {}", route_evl, bundle.size);
+                    flagged += 1;
+                }
+                // charge the bundle to the mirror the same way the router did
+                let indices: Vec<usize> = route
+                    .iter()
+                    .filter_map(|hop| {
+                        mirror.iter().position(|c| {
+                            c.info.from == hop.from && c.info.to == hop.to
+                                && c.info.start == hop.start && c.info.end == hop.end
+                        })
+                    })
+                    .collect();
+                let mut time = at_time;
+                for idx in indices {
+                    let contact = &mut mirror[idx];
+                    match contact.manager.schedule_tx(&contact.info, time, bundle) {
+                        Some(data) => time = data.arrival,
+                        None => {
+                            println!("  FLAG: contact {} -> {} can not schedule the bundle", contact.info.from, contact.info.to);
+                            flagged += 1;
+                            break;
+                        }
+                    }
+                }
+            }
+            None => {
+                let destination = bundle.destinations[0];
+                match best_candidate(&mirror, bundle, at_time, destination, max_hops) {
+                    Some(metrics) => {
+                        println!(
+                            "  No route found, best candidate: {} hops, arrival {}, R.EVL = {}",
+                            metrics.hops, metrics.arrival, metrics.evl
+                        );
+                        if metrics.evl >= bundle.size {
+                            println!("  FLAG: rejected although R.EVL {} >= bundle size {}", metrics.evl, bundle.size);
+                            flagged += 1;
+                        }
+                    }
+                    None => println!("  No route found, no candidate route either"),
+                }
+            }
+        }
+    }
+    flagged
+}
+
+/// Best candidate route (tutorial order) of the mirror regardless of volumes.
+fn best_candidate(
+    contacts: &[Contact<NoManagement, EVLManager>],
+    bundle: &Bundle,
+    at_time: Date,
+    destination: NodeID,
+    max_hops: usize,
+) -> Option<RouteMetrics> {
+    let mut candidates: Vec<Vec<usize>> = Vec::new();
+    let mut path: Vec<usize> = Vec::new();
+    let mut visited: Vec<NodeID> = vec![bundle.source];
+    candidate_routes(contacts, bundle.source, destination, max_hops, &mut visited, &mut path, &mut candidates);
+
+    let policy = SelectionPolicy::EarliestArrival;
+    candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, route)| {
+            let mut metrics = project(contacts, route, index, at_time, bundle)?;
+            let hops: Vec<AsabrContact> = route
+                .iter()
+                .map(|&c| AsabrContact {
+                    info: &contacts[c].info,
+                    manager: &contacts[c].manager,
+                    priority: bundle.priority,
+                })
+                .collect();
+            let refs: Vec<&AsabrContact> = hops.iter().collect();
+            metrics.evl = compute_route_evl(&refs);
+            Some(metrics)
+        })
+        .min_by(|a, b| policy.compare(a, b))
+}
+
+/// Depth first enumeration of the loop free contact sequences from `node` to `destination`.
+fn candidate_routes(
+    contacts: &[Contact<NoManagement, EVLManager>],
+    node: NodeID,
+    destination: NodeID,
+    max_hops: usize,
+    visited: &mut Vec<NodeID>,
+    path: &mut Vec<usize>,
+    candidates: &mut Vec<Vec<usize>>,
+) {
+    if path.len() >= max_hops {
+        return;
+    }
+    // contacts must follow each other in time
+    let not_before = path.last().map_or(f64::MIN, |&c| contacts[c].info.start);
+    for (idx, contact) in contacts.iter().enumerate() {
+        if contact.info.from != node || contact.info.end <= not_before || visited.contains(&contact.info.to) {
+            continue;
+        }
+        path.push(idx);
+        if contact.info.to == destination {
+            candidates.push(path.clone());
+        } else {
+            visited.push(contact.info.to);
+            candidate_routes(contacts, contact.info.to, destination, max_hops, visited, path, candidates);
+            visited.pop();
+        }
+        path.pop();
+    }
+}
+
+/// Same projection as route_selection::route_metrics, with the bundle expiration. R.EVL is left to the caller.
+fn project(
+    contacts: &[Contact<NoManagement, EVLManager>],
+    route: &[usize],
+    index: usize,
+    at_time: Date,
+    bundle: &Bundle,
+) -> Option<RouteMetrics> {
+    let mut time = at_time;
+    let mut termination = f64::INFINITY;
+    for &c in route {
+        let (info, manager) = (&contacts[c].info, &contacts[c].manager);
+        let tx_start = time.max(info.start);
+        let tx_end = tx_start + bundle.size / manager.rate;
+        if tx_end > info.end {
+            return None;
+        }
+        time = tx_end + manager.delay;
+        termination = termination.min(info.end);
+    }
+    if time > bundle.expiration {
+        return None;
+    }
+    Some(RouteMetrics {
+        index,
+        arrival: time,
+        hops: route.len(),
+        termination,
+        evl: 0.0,
+        proximate_node: contacts[*route.first()?].info.to as u32,
+    })
+}
@@ -1,53 +1,121 @@
 //! test main for my_r_evl.rs on R.EVL of Candidate Routes Construction.
+//! Usage: main [--policy <arrival|hops|termination|evl>] [<cp_file> [<route> ...]]
+//! where a route is a comma separated list of contact ids (e.g. 1,2,3).
+//! Without a cp_file, the four contacts of the tutorial example are used.
+mod cp_loader;
 mod my_r_evl;
-use my_r_evl::{Contact, compute_route_evl};
+mod route_selection;
+use cp_loader::load_contacts;
+use my_r_evl::{Contact, ContactStore};
+use route_selection::{route_metrics, select_route, SelectionPolicy};
+use std::env;
 
 fn main() {
-    let contact1 = Contact { id: 1, start: 0.0, end: 10.0, data_rate: 1.0, max_volume: 10.0 };
-    let contact2 = Contact { id: 2, start: 10.0, end: 20.0, data_rate: 1.0, max_volume: 20.0 };
-    let contact3 = Contact { id: 3, start: 20.0, end: 25.0, data_rate: 2.0, max_volume: 10.0 };
-    let contact4 = Contact { id: 4, start: 25.0, end: 30.0, data_rate: 1.0, max_volume: 20.0 };
-
-    // let route = vec![contact1.clone(), contact2.clone()];
-    // let evl1 = contact1.max_volume.min(contact1.data_rate * (contact1.end - contact1.start));
-    // let evl2 = contact2.max_volume.min(contact2.data_rate * (contact2.end - contact2.start));
-    // println!("Contact {} EVL: {}", contact1.id, evl1);
-    // println!("Contact {} EVL: {}", contact2.id, evl2);
-
-    // let route_evl = compute_route_evl(&route);
-    // println!("Route EVL = {}", route_evl);
-
-    let bundle_evc = 10.0;
-    println!("Bundle EVC = {}", bundle_evc);
+    let mut args: Vec<String> = env::args().collect();
+    let mut policy = SelectionPolicy::EarliestArrival;
+    if let Some(pos) = args.iter().position(|a| a == "--policy") {
+        if pos + 1 >= args.len() {
+            eprintln!("ERR: --policy needs a value");
+            std::process::exit(1);
+        }
+        policy = args[pos + 1].parse().unwrap_or_else(|e| {
+            eprintln!("ERR: {}", e);
+            std::process::exit(1);
+        });
+        args.drain(pos..pos + 2);
+    }
+    println!("Route selection policy: {:?}", policy);
+    let (mut store, paths) = if args.len() > 1 {
+        let contacts = load_contacts(&args[1], 0).unwrap_or_else(|e| {
+            eprintln!("ERR: {}", e);
+            std::process::exit(1);
+        });
+        println!("Working with cp: {} ({} contacts)", args[1], contacts.len());
+        for c in contacts.iter() {
+            println!(
+                "  Contact {}: {} -> {}, [{}, {}], rate {}, volume {}, delay {}",
+                c.id, c.from, c.to, c.start, c.end, c.data_rate, c.max_volume, c.delay
+            );
+        }
+        let paths: Vec<Vec<u32>> = args[2..]
+            .iter()
+            .map(|r| {
+                r.split(',')
+                    .map(|id| id.trim().parse::<u32>().unwrap_or_else(|_| {
+                        eprintln!("ERR: Invalid route {}", r);
+                        std::process::exit(1);
+                    }))
+                    .collect()
+            })
+            .collect();
+        (ContactStore::new(contacts), paths)
+    } else {
+        let store = ContactStore::new(vec![
+            Contact { id: 1, from: 0, to: 1, start: 0.0, end: 10.0, data_rate: 1.0, max_volume: 10.0, delay: 0.0 },
+            Contact { id: 2, from: 1, to: 2, start: 10.0, end: 20.0, data_rate: 1.0, max_volume: 20.0, delay: 0.0 },
+            Contact { id: 3, from: 2, to: 3, start: 20.0, end: 25.0, data_rate: 2.0, max_volume: 10.0, delay: 0.0 },
+            Contact { id: 4, from: 3, to: 4, start: 25.0, end: 30.0, data_rate: 1.0, max_volume: 20.0, delay: 0.0 },
+        ]);
+        // routes as contact ids, the volumes live in the store
+        let paths: Vec<Vec<u32>> = vec![
+            vec![1, 2],
+            vec![1, 2, 3],
+            vec![2, 3],
+            vec![3, 4],
+            vec![1, 2, 3, 4],
+        ];
+        (store, paths)
+    };
 
-    // if route_evl >= bundle_evc {
-    //     println!("Route can forward the bundle.");
-    // } else {
-    //     println!("Route cannot forward the bundle (EVL < B.EVC).");
-    // }
+    // bundles are forwarded one after another, each one sees the volume left by the previous ones
+    let bundles_evc = vec![10.0, 5.0, 5.0, 10.0];
+    let at_time = 0.0;
 
-    let paths = vec![
-        vec![&contact1, &contact2],
-        vec![&contact1, &contact2, &contact3],
-        vec![&contact2, &contact3],
-        vec![&contact3, &contact4],
-        vec![&contact1, &contact2, &contact3, &contact4],
-    ];
+    for (b, &bundle_evc) in bundles_evc.iter().enumerate() {
+        println!("\n=== Bundle {} EVC = {} ===", b + 1, bundle_evc);
+        for (i, ids) in paths.iter().enumerate() {
+            println!("\nPath {}:", i + 1);
+            let route = match store.route(ids) {
+                Some(route) => route,
+                None => {
+                    println!("  Unknown contact id in route {:?}", ids);
+                    continue;
+                }
+            };
+            for c in route.iter() {
+                let evl = c.max_volume.min(c.data_rate * (c.end - c.start));
+                println!("  Contact {} EVL: {}", c.id, evl);
+            }
 
-    for (i, route) in paths.iter().enumerate() {
-        println!("\nPath {}:", i + 1);
-        for c in route {
-            let evl = c.max_volume.min(c.data_rate * (c.end - c.start));
-            println!("  Contact {} EVL: {}", c.id, evl);
+            let route_evl = store.route_evl(ids);
+            println!("  Route EVL = {}", route_evl);
+            match route_metrics(&route, i, at_time, bundle_evc) {
+                Some(m) => println!(
+                    "  Arrival = {}, hops = {}, termination = {}, proximate node = {}",
+                    m.arrival, m.hops, m.termination, m.proximate_node
+                ),
+                None => println!("  A contact ends before the bundle is transmitted."),
+            }
+            if route_evl >= bundle_evc {
+                println!("Route can forward the bundle.");
+            } else {
+                println!("Route cannot forward the bundle (EVL < EVC).");
+            }
         }
 
-        let route_evl = compute_route_evl(route);
-        println!("  Route EVL = {}", route_evl);
-        // println!("  Bundle EVC = {}", bundle_evc);
-        if route_evl >= bundle_evc {
-            println!("Route can forward the bundle.");
-        } else {
-            println!("Route cannot forward the bundle (EVL < EVC).");
+        // charge the bundle to the best route that can forward it
+        match select_route(&store, &paths, at_time, bundle_evc, policy) {
+            Some(m) => {
+                store.forward(&paths[m.index], bundle_evc);
+                println!(
+                    "\nBundle {} forwarded on path {} to proximate node {} (arrival {}, {} hops, termination {}), remaining volumes:",
+                    b + 1, m.index + 1, m.proximate_node, m.arrival, m.hops, m.termination
+                );
+                for c in store.contacts.iter() {
+                    println!("  Contact {} max_volume: {}", c.id, c.max_volume);
+                }
+            }
+            None => println!("\nBundle {} cannot be forwarded on any path.", b + 1),
         }
     }
 }
@@ -4,32 +4,60 @@
 /// effective_stop = min(contact.end, all following contact end)
 /// C.EVL = min(contact.max_volume, data_rate * effective_duration) without priority impl for C.MAV
 /// R.EVL = min(all C.EVL)
-// #[derive(Debug，Clone)]
+#[derive(Debug, Clone)]
 pub struct Contact {
     pub id: u32,
+    pub from: u32,
+    pub to: u32,
     pub start: f64,
     pub end: f64,
     pub data_rate: f64,
     pub max_volume: f64,
+    pub delay: f64,
 }
 
-pub fn compute_route_evl(route: &[&Contact]) -> f64 { //&[&Contact] borrow instead of clone
+/// What R.EVL needs to know about a contact, so that the same computation runs on the local
+/// `Contact` and on A-SABR contacts (see asabr_contact.rs).
+pub trait EvlContact {
+    fn start(&self) -> f64;
+    fn end(&self) -> f64;
+    fn rate(&self) -> f64;
+    /// C.MAV, the volume still available on the contact.
+    fn volume(&self) -> f64;
+}
+
+impl EvlContact for Contact {
+    fn start(&self) -> f64 {
+        self.start
+    }
+    fn end(&self) -> f64 {
+        self.end
+    }
+    fn rate(&self) -> f64 {
+        self.data_rate
+    }
+    fn volume(&self) -> f64 {
+        self.max_volume
+    }
+}
+
+pub fn compute_route_evl<C: EvlContact>(route: &[&C]) -> f64 { //&[&C] borrow instead of clone
     // R.EVL starts with inf
     let mut route_evl = f64::INFINITY;
 
     for (i, contact) in route.iter().enumerate() {
-        let mut effective_stop = contact.end;
+        let mut effective_stop = contact.end();
         for succ in route.iter().skip(i + 1) {  // all following contacts
-            if succ.end < effective_stop {
-                effective_stop = succ.end;
+            if succ.end() < effective_stop {
+                effective_stop = succ.end();
             }
         }
-        let effective_duration = if effective_stop > contact.start {
-            effective_stop - contact.start
+        let effective_duration = if effective_stop > contact.start() {
+            effective_stop - contact.start()
         } else {
             0.0
         };
-        let contact_evl = contact.max_volume.min(contact.data_rate * effective_duration);
+        let contact_evl = contact.volume().min(contact.rate() * effective_duration);
         if contact_evl < route_evl {
          route_evl = contact_evl;
         }
@@ -41,3 +69,48 @@ pub fn compute_route_evl(route: &[&Contact]) -> f64 { //&[&Contact] borrow inste
      route_evl
     }
 }
+
+
+/// Contacts owned by the simulation, so that forwarding a bundle can charge its volume.
+/// P18: whenever a bundle B is enqueued for transmission via a route,
+/// the C.MAV of all contacts in that route is reduced by B.EVC (no priority levels here).
+pub struct ContactStore {
+    pub contacts: Vec<Contact>,
+}
+
+impl ContactStore {
+    pub fn new(contacts: Vec<Contact>) -> Self {
+        Self { contacts }
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Contact> {
+        self.contacts.iter().find(|c| c.id == id)
+    }
+
+    /// Resolves a route given as contact ids, None if an id is unknown.
+    pub fn route(&self, ids: &[u32]) -> Option<Vec<&Contact>> {
+        ids.iter().map(|&id| self.get(id)).collect()
+    }
+
+    /// R.EVL of a route given as contact ids with the current (possibly consumed) volumes.
+    pub fn route_evl(&self, ids: &[u32]) -> f64 {
+        match self.route(ids) {
+            Some(route) => compute_route_evl(&route),
+            None => 0.0,
+        }
+    }
+
+    /// Forwards a bundle along the route: subtract B.EVC from every contact of the route.
+    /// Nothing is charged and `false` is returned if R.EVL < B.EVC.
+    pub fn forward(&mut self, ids: &[u32], bundle_evc: f64) -> bool {
+        if ids.is_empty() || self.route_evl(ids) < bundle_evc {
+            return false;
+        }
+        for contact in self.contacts.iter_mut() {
+            if ids.contains(&contact.id) {
+                contact.max_volume = (contact.max_volume - bundle_evc).max(0.0);
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,109 @@
+//! Route selection among the candidate routes kept after the R.EVL filter, P17 CGR tutorial.
+//! The tutorial prefers the earliest projected arrival, then the fewest hops,
+//! then the latest route termination time (min of all contact ends), and finally the
+//! lowest proximate node id. Other policies only change the order of the first three criteria.
+use std::{cmp::Ordering, str::FromStr};
+
+use crate::my_r_evl::{compute_route_evl, Contact, ContactStore};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelectionPolicy {
+    /// Earliest arrival, fewest hops, latest termination (tutorial).
+    EarliestArrival,
+    /// Fewest hops, earliest arrival, latest termination.
+    FewestHops,
+    /// Latest termination, earliest arrival, fewest hops.
+    LatestTermination,
+    /// Largest R.EVL, earliest arrival, fewest hops.
+    LargestEvl,
+}
+
+impl FromStr for SelectionPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "arrival" | "tutorial" => Ok(Self::EarliestArrival),
+            "hops" => Ok(Self::FewestHops),
+            "termination" => Ok(Self::LatestTermination),
+            "evl" => Ok(Self::LargestEvl),
+            _ => Err(format!(
+                "Unknown selection policy {} (arrival, hops, termination, evl)",
+                s
+            )),
+        }
+    }
+}
+
+/// What the selection needs to know about a candidate route for a given bundle.
+#[derive(Debug, Clone)]
+pub struct RouteMetrics {
+    /// Position of the route in the candidate list.
+    pub index: usize,
+    /// Projected arrival time at the last node.
+    pub arrival: f64,
+    pub hops: usize,
+    /// Route termination time, min of all contact ends.
+    pub termination: f64,
+    pub evl: f64,
+    /// Receiving node of the first contact, the node the bundle is forwarded to.
+    pub proximate_node: u32,
+}
+
+/// Projects the bundle transmission hop after hop from `at_time`.
+/// Returns None if a contact ends before the bundle is fully sent (or the route is empty).
+pub fn route_metrics(route: &[&Contact], index: usize, at_time: f64, bundle_evc: f64) -> Option<RouteMetrics> {
+    let first = route.first()?;
+    let mut time = at_time;
+    let mut termination = f64::INFINITY;
+    for contact in route.iter() {
+        let tx_start = time.max(contact.start);
+        let tx_end = tx_start + bundle_evc / contact.data_rate;
+        if tx_end > contact.end {
+            return None;
+        }
+        time = tx_end + contact.delay;
+        termination = termination.min(contact.end);
+    }
+    Some(RouteMetrics {
+        index,
+        arrival: time,
+        hops: route.len(),
+        termination,
+        evl: compute_route_evl(route),
+        proximate_node: first.to,
+    })
+}
+
+impl SelectionPolicy {
+    /// `Ordering::Less` if `a` is the better route.
+    pub fn compare(&self, a: &RouteMetrics, b: &RouteMetrics) -> Ordering {
+        let arrival = a.arrival.total_cmp(&b.arrival);
+        let hops = a.hops.cmp(&b.hops);
+        let termination = b.termination.total_cmp(&a.termination);
+        let ordering = match self {
+            Self::EarliestArrival => arrival.then(hops).then(termination),
+            Self::FewestHops => hops.then(arrival).then(termination),
+            Self::LatestTermination => termination.then(arrival).then(hops),
+            Self::LargestEvl => b.evl.total_cmp(&a.evl).then(arrival).then(hops),
+        };
+        ordering.then(a.proximate_node.cmp(&b.proximate_node))
+    }
+}
+
+/// Chooses the best route among `paths` (contact ids) that can forward the bundle,
+/// i.e. R.EVL >= B.EVC and every contact still open when the bundle reaches it.
+pub fn select_route(
+    store: &ContactStore,
+    paths: &[Vec<u32>],
+    at_time: f64,
+    bundle_evc: f64,
+    policy: SelectionPolicy,
+) -> Option<RouteMetrics> {
+    paths
+        .iter()
+        .enumerate()
+        .filter_map(|(i, ids)| route_metrics(&store.route(ids)?, i, at_time, bundle_evc))
+        .filter(|m| m.evl >= bundle_evc)
+        .min_by(|a, b| policy.compare(a, b))
+}